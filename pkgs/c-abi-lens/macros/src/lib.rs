@@ -0,0 +1,167 @@
+//! Procedural macro generating lens-modules at Rust compile time, see [`lens!`]
+
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use std::{cell::OnceCell, path::PathBuf};
+
+use c_abi_lens::{Builder, ClangSession, OutputFormat};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    Ident, Lit, LitBool, LitStr, Token,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+};
+
+thread_local! {
+    /// libclang can only be loaded once at a time, hence it is shared by all invocations
+    static SESSION: OnceCell<Result<ClangSession, String>> = const { OnceCell::new() };
+}
+
+/// Generate the Rust lens-module for the C types declared in a header
+///
+/// Expands to the same module as `c-abi-lens --output-format rust`, i.e. to a `no_std` compatible
+/// module free of unsafe code, named by the `prefix` (`cal` by default). The first argument is the
+/// path of the header, relative to the directory containing the invoking crate's `Cargo.toml`.
+/// It is followed by any number of options, each of which corresponds to the command line option
+/// of the same name:
+///
+/// - `target = "<TRIPLE>"`: the foreign target, passed to clang as `--target=<TRIPLE>`
+/// - `prefix = "<NAME>"`: the name of the module
+/// - `clang_arg = "<ARG>"`: an argument passed verbatim to clang, may be given multiple times
+/// - `include_type`, `exclude_type`, `exclude_field`, `allow_header` and `macros`, each with a
+///   regex and each of which may be given multiple times
+/// - `main_file_only`, `deref_helpers` and `comment`, each with a `bool`
+///
+/// The arguments from the `EXTRA_CLANG_ARGS` and `BINDGEN_EXTRA_CLANG_ARGS` environment variables
+/// are passed to clang first, just as the binary does. Errors reported by clang while parsing the
+/// header are compile errors, and the crate is rebuilt whenever the header changes.
+///
+/// ```ignore
+/// c_abi_lens_macros::lens!(
+///     "include/ARINC653-wasm.h",
+///     target = "wasm32-unknown-wasi",
+///     prefix = "apex",
+///     macros = "SYSTEM_LIMIT_.*",
+/// );
+///
+/// let memory = [0u8; 64];
+/// let max_nb_message = apex::get__QUEUING_PORT_STATUS_TYPE__MAX_NB_MESSAGE(&memory, 0)?;
+/// ```
+#[proc_macro]
+pub fn lens(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let arguments = parse_macro_input!(input as LensArguments);
+    match expand(arguments) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// The arguments of an invocation of [`lens!`]
+struct LensArguments {
+    header: LitStr,
+    options: Vec<(Ident, Lit)>,
+}
+
+impl Parse for LensArguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let header = input.parse()?;
+        let mut options = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key = input.parse()?;
+            input.parse::<Token![=]>()?;
+            options.push((key, input.parse()?));
+        }
+        Ok(Self { header, options })
+    }
+}
+
+/// Apply one option to the `builder`
+fn apply_option(builder: Builder, key: &Ident, value: &Lit) -> syn::Result<Builder> {
+    let string = || match value {
+        Lit::Str(s) => Ok(s.value()),
+        _ => Err(syn::Error::new_spanned(value, "expected a string literal")),
+    };
+    let bool = || match value {
+        Lit::Bool(LitBool { value, .. }) => Ok(*value),
+        _ => Err(syn::Error::new_spanned(value, "expected `true` or `false`")),
+    };
+
+    Ok(match key.to_string().as_str() {
+        "target" => builder.clang_arg(format!("--target={}", string()?)),
+        "prefix" => builder.prefix(string()?),
+        "clang_arg" => builder.clang_arg(string()?),
+        "include_type" => builder.include_type(string()?),
+        "exclude_type" => builder.exclude_type(string()?),
+        "exclude_field" => builder.exclude_field(string()?),
+        "allow_header" => builder.allow_header(string()?),
+        "macros" => builder.macro_pattern(string()?),
+        "main_file_only" => builder.main_file_only(bool()?),
+        "deref_helpers" => builder.deref_helpers(bool()?),
+        "comment" => builder.comment(bool()?),
+        _ => return Err(syn::Error::new_spanned(key, "unknown option")),
+    })
+}
+
+/// Generate the lens-module, or the compile errors preventing it
+fn expand(LensArguments { header, options }: LensArguments) -> syn::Result<TokenStream> {
+    let error = |message: String| syn::Error::new(Span::call_site(), message);
+
+    // relative paths are relative to the invoking crate, not to wherever rustc runs
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|e| error(format!("unable to locate the invoking crate: {e}")))?;
+    let header_path = PathBuf::from(manifest_dir).join(header.value());
+    let header_path = header_path
+        .canonicalize()
+        .map_err(|e| syn::Error::new_spanned(&header, format!("{header_path:?}: {e}")))?;
+
+    let env_clang_args = c_abi_lens::clang_args_from_env().map_err(|e| error(format!("{e:#}")))?;
+    let mut builder = Builder::new(&header_path)
+        .clang_args(env_clang_args)
+        .output_format(OutputFormat::Rust);
+    for (key, value) in &options {
+        builder = apply_option(builder, key, value)?;
+    }
+
+    let code = SESSION.with(|session| {
+        let session = session
+            .get_or_init(|| ClangSession::new().map_err(|e| format!("{e:#}")))
+            .as_ref()
+            .map_err(|e| error(e.clone()))?;
+        let lens = builder.lens(session).map_err(|e| error(format!("{e:#}")))?;
+
+        // all errors at once, rather than just the first one
+        let errors = lens
+            .diagnostics()
+            .iter()
+            .filter(|d| d.is_error)
+            .map(|d| error(format!("clang: {}", d.message)))
+            .reduce(|mut errors, e| {
+                errors.combine(e);
+                errors
+            });
+        if let Some(errors) = errors {
+            return Err(errors);
+        }
+
+        let code = lens.generate().map_err(|e| error(format!("{e:#}")))?;
+        Ok(code)
+    })?;
+
+    let module: TokenStream = code
+        .parse()
+        .map_err(|e| error(format!("the generated code does not parse: {e}")))?;
+
+    // rebuild whenever the header changes, included as bytes as it need not be UTF-8
+    let header_path = header_path.to_string_lossy();
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#header_path);
+
+        #module
+    })
+}
@@ -0,0 +1,28 @@
+// the lens of the smoke test header of the c-abi-lens crate, for the host
+c_abi_lens_macros::lens!(
+    "../tests/smoke_test.h",
+    prefix = "smoke",
+    include_type = "IntegerWithPadding",
+    macros = "SMOKE_TEST_.*",
+    comment = true,
+);
+
+/// Verify that the expanded module is usable and agrees with the host's layout
+#[test]
+fn lens_round_trip() {
+    #[repr(C)]
+    struct IntegerWithPadding {
+        a: u8,
+        b: i64,
+    }
+
+    assert_eq!(
+        smoke::sizeof__IntegerWithPadding(),
+        size_of::<IntegerWithPadding>()
+    );
+    assert_eq!(smoke::SMOKE_TEST_NEGATIVE, -42);
+
+    let mut memory = vec![0u8; smoke::sizeof__IntegerWithPadding()];
+    smoke::set__IntegerWithPadding__b(&mut memory, 0, -7).unwrap();
+    assert_eq!(smoke::get__IntegerWithPadding__b(&memory, 0), Ok(-7));
+}
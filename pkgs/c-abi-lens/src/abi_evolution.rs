@@ -0,0 +1,660 @@
+//! Classification of the layout changes between two revisions of a header for the same target
+
+use std::fmt::Write;
+
+use color_eyre::Result;
+use serde::Serialize;
+
+use crate::{
+    code_gen::{
+        EnumLayout, FieldKind, FieldLayout, LensLayout, RecordKind, RecordLayout,
+        RepresentableCType, TypeLayout,
+    },
+    layout_diff::{field_dimensions, field_element_repr, field_kind_name, signedness},
+};
+
+/// The layout changes of all types between an old and a new revision of a header
+#[derive(Debug, Clone, Serialize)]
+pub struct EvolutionReport {
+    /// The target both revisions were laid out for
+    pub target: String,
+
+    /// Whether no type changed in a way that breaks the foreign ABI
+    pub compatible: bool,
+    pub types: Vec<TypeEvolution>,
+}
+
+/// The layout changes of one type
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeEvolution {
+    pub name: String,
+    pub verdict: Verdict,
+    pub changes: Vec<Change>,
+}
+
+/// How a type changed, as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Unchanged,
+
+    /// Instances laid out according to the old revision are still valid in the new revision
+    Compatible,
+
+    /// Instances laid out according to the old revision are misinterpreted by the new revision
+    Breaking,
+}
+
+/// One change of a type or one of its fields
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// Path of the changed field or enumerator, empty if the type itself changed
+    pub path: Vec<String>,
+    pub kind: ChangeKind,
+
+    /// The old property, if it is of interest
+    pub old: Option<String>,
+
+    /// The new property, if it is of interest
+    pub new: Option<String>,
+}
+
+/// The kinds of changes, each of which is either compatible or breaking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    AddedType,
+    RemovedType,
+    ChangedTypeKind,
+    GrownRecord,
+    ShrunkRecord,
+    ChangedAlignment,
+
+    /// A field placed behind all bytes of the old record
+    AppendedField,
+
+    /// A field placed within the bytes of the old record, e.g. in former padding
+    InsertedField,
+
+    /// A field of a new name at the same offset, of the same type as the removed field
+    RenamedField,
+    RemovedField,
+    MovedField,
+    ResizedField,
+    ChangedSignedness,
+    ChangedFieldType,
+    AddedEnumerator,
+    RemovedEnumerator,
+    ChangedEnumeratorValue,
+    ChangedEnumRepresentation,
+}
+
+impl ChangeKind {
+    fn description(&self) -> &'static str {
+        match self {
+            Self::AddedType => "added type",
+            Self::RemovedType => "removed type",
+            Self::ChangedTypeKind => "changed kind of type",
+            Self::GrownRecord => "grown size in bytes",
+            Self::ShrunkRecord => "shrunk size in bytes",
+            Self::ChangedAlignment => "changed alignment in bytes",
+            Self::AppendedField => "appended field",
+            Self::InsertedField => "inserted field",
+            Self::RenamedField => "renamed field",
+            Self::RemovedField => "removed field",
+            Self::MovedField => "moved field, offset in bits",
+            Self::ResizedField => "resized field",
+            Self::ChangedSignedness => "changed signedness",
+            Self::ChangedFieldType => "changed type of field",
+            Self::AddedEnumerator => "added enumerator",
+            Self::RemovedEnumerator => "removed enumerator",
+            Self::ChangedEnumeratorValue => "changed value of enumerator",
+            Self::ChangedEnumRepresentation => "changed representation of enum",
+        }
+    }
+
+    /// The verdict for a type with only this change
+    pub fn verdict(&self) -> Verdict {
+        match self {
+            Self::AddedType
+            | Self::GrownRecord
+            | Self::AppendedField
+            | Self::RenamedField
+            | Self::AddedEnumerator => Verdict::Compatible,
+            Self::RemovedType
+            | Self::ChangedTypeKind
+            | Self::ShrunkRecord
+            | Self::ChangedAlignment
+            | Self::InsertedField
+            | Self::RemovedField
+            | Self::MovedField
+            | Self::ResizedField
+            | Self::ChangedSignedness
+            | Self::ChangedFieldType
+            | Self::RemovedEnumerator
+            | Self::ChangedEnumeratorValue
+            | Self::ChangedEnumRepresentation => Verdict::Breaking,
+        }
+    }
+}
+
+impl EvolutionReport {
+    /// Classify the changes of all types from the `old` to the `new` revision
+    pub fn new(old: &LensLayout, new: &LensLayout) -> Result<Self> {
+        let mut types = Vec::new();
+
+        for old_type in &old.types {
+            let new_type = new
+                .types
+                .iter()
+                .find(|t| type_name(t) == type_name(old_type));
+
+            let mut changes = Vec::new();
+            match (old_type, new_type) {
+                (_, None) => changes.push(Change::new(&[], ChangeKind::RemovedType, None, None)),
+                (TypeLayout::Record(o), Some(TypeLayout::Record(n))) => {
+                    evolve_record(&mut changes, o, n)?
+                }
+                (TypeLayout::Enum(o), Some(TypeLayout::Enum(n))) => evolve_enum(&mut changes, o, n),
+                (_, Some(new_type)) => changes.push(Change::new(
+                    &[],
+                    ChangeKind::ChangedTypeKind,
+                    Some(type_kind(old_type).to_string()),
+                    Some(type_kind(new_type).to_string()),
+                )),
+            }
+
+            types.push(TypeEvolution::new(type_name(old_type), changes));
+        }
+
+        // types only present in the new revision
+        for new_type in &new.types {
+            if !old
+                .types
+                .iter()
+                .any(|t| type_name(t) == type_name(new_type))
+            {
+                types.push(TypeEvolution::new(
+                    type_name(new_type),
+                    vec![Change::new(&[], ChangeKind::AddedType, None, None)],
+                ));
+            }
+        }
+
+        Ok(Self {
+            target: new.target.triple.clone(),
+            compatible: types.iter().all(|t| t.verdict != Verdict::Breaking),
+            types,
+        })
+    }
+
+    /// Render a human readable report
+    pub fn to_report(&self) -> String {
+        let mut report = format!("comparing old with new revision on {}\n\n", self.target);
+
+        for type_evolution in &self.types {
+            let verdict = match type_evolution.verdict {
+                Verdict::Unchanged => "unchanged",
+                Verdict::Compatible => "compatible",
+                Verdict::Breaking => "BREAKING",
+            };
+            let _ = writeln!(report, "{}: {verdict}", type_evolution.name);
+
+            for Change {
+                path,
+                kind,
+                old,
+                new,
+            } in &type_evolution.changes
+            {
+                let severity = match kind.verdict() {
+                    Verdict::Breaking => "breaking",
+                    _ => "compatible",
+                };
+                let mut line = format!("    [{severity}] {}", kind.description());
+                if !path.is_empty() {
+                    let _ = write!(line, " {}", path.join("."));
+                }
+                match (old, new) {
+                    (Some(old), Some(new)) => {
+                        let _ = write!(line, ": {old} -> {new}");
+                    }
+                    (Some(old), None) => {
+                        let _ = write!(line, ": {old}");
+                    }
+                    (None, Some(new)) => {
+                        let _ = write!(line, ": {new}");
+                    }
+                    (None, None) => {}
+                }
+                let _ = writeln!(report, "{line}");
+            }
+        }
+
+        let count = |verdict| self.types.iter().filter(|t| t.verdict == verdict).count();
+        let _ = write!(
+            report,
+            "\n{} unchanged, {} compatible, {} breaking\n",
+            count(Verdict::Unchanged),
+            count(Verdict::Compatible),
+            count(Verdict::Breaking)
+        );
+
+        report
+    }
+}
+
+impl TypeEvolution {
+    fn new(name: &str, changes: Vec<Change>) -> Self {
+        Self {
+            name: name.to_owned(),
+            verdict: changes
+                .iter()
+                .map(|c| c.kind.verdict())
+                .max()
+                .unwrap_or(Verdict::Unchanged),
+            changes,
+        }
+    }
+}
+
+impl Change {
+    fn new(path: &[String], kind: ChangeKind, old: Option<String>, new: Option<String>) -> Self {
+        Self {
+            path: path.to_vec(),
+            kind,
+            old,
+            new,
+        }
+    }
+}
+
+fn type_name(type_layout: &TypeLayout) -> &str {
+    match type_layout {
+        TypeLayout::Record(r) => &r.name,
+        TypeLayout::Enum(e) => &e.name,
+    }
+}
+
+fn type_kind(type_layout: &TypeLayout) -> &'static str {
+    match type_layout {
+        TypeLayout::Record(_) => "record",
+        TypeLayout::Enum(_) => "enum",
+    }
+}
+
+/// Push a [`Change`] if `old` and `new` differ
+fn push_if_changed<T: PartialEq + ToString>(
+    changes: &mut Vec<Change>,
+    path: &[String],
+    kind: ChangeKind,
+    old: T,
+    new: T,
+) {
+    if old != new {
+        changes.push(Change::new(
+            path,
+            kind,
+            Some(old.to_string()),
+            Some(new.to_string()),
+        ));
+    }
+}
+
+/// Whether two fields share the same layout, regardless of their names
+fn same_layout(old: &FieldLayout, new: &FieldLayout) -> bool {
+    old.offset_bits == new.offset_bits && old.align_bytes == new.align_bytes && old.kind == new.kind
+}
+
+fn evolve_record(changes: &mut Vec<Change>, old: &RecordLayout, new: &RecordLayout) -> Result<()> {
+    if old.kind != new.kind {
+        changes.push(Change::new(
+            &[],
+            ChangeKind::ChangedTypeKind,
+            Some(format!("{:?}", old.kind)),
+            Some(format!("{:?}", new.kind)),
+        ));
+    }
+
+    let size_change = match new.size_bytes < old.size_bytes {
+        true => ChangeKind::ShrunkRecord,
+        false => ChangeKind::GrownRecord,
+    };
+    push_if_changed(changes, &[], size_change, old.size_bytes, new.size_bytes);
+    push_if_changed(
+        changes,
+        &[],
+        ChangeKind::ChangedAlignment,
+        old.align_bytes,
+        new.align_bytes,
+    );
+
+    let old_end_bits = old.size_bytes * 8;
+    let added_fields: Vec<_> = new
+        .fields
+        .iter()
+        .filter(|n| !old.fields.iter().any(|o| o.path == n.path))
+        .collect();
+    let mut renamed_fields = Vec::new();
+
+    for old_field in &old.fields {
+        if let Some(new_field) = new.fields.iter().find(|f| f.path == old_field.path) {
+            evolve_field(changes, old_field, new_field)?;
+            continue;
+        }
+
+        // a removed field with an added field of the same layout in its place was renamed
+        match added_fields
+            .iter()
+            .find(|n| same_layout(old_field, n) && !renamed_fields.contains(&n.path))
+        {
+            Some(new_field) => {
+                renamed_fields.push(new_field.path.clone());
+                changes.push(Change::new(
+                    &old_field.path,
+                    ChangeKind::RenamedField,
+                    None,
+                    Some(new_field.name()),
+                ));
+            }
+            None => changes.push(Change::new(
+                &old_field.path,
+                ChangeKind::RemovedField,
+                None,
+                None,
+            )),
+        }
+    }
+
+    for new_field in added_fields {
+        if renamed_fields.contains(&new_field.path) {
+            continue;
+        }
+
+        // all members of a union overlap anyways, the old ones are not affected by a new one
+        let kind = match new.kind == RecordKind::Union || new_field.offset_bits >= old_end_bits {
+            true => ChangeKind::AppendedField,
+            false => ChangeKind::InsertedField,
+        };
+        changes.push(Change::new(
+            &new_field.path,
+            kind,
+            None,
+            Some(format!("at offset {} bits", new_field.offset_bits)),
+        ));
+    }
+
+    Ok(())
+}
+
+fn evolve_field(changes: &mut Vec<Change>, old: &FieldLayout, new: &FieldLayout) -> Result<()> {
+    let path = &old.path;
+
+    push_if_changed(
+        changes,
+        path,
+        ChangeKind::MovedField,
+        old.offset_bits,
+        new.offset_bits,
+    );
+
+    let (old_kind, new_kind) = (field_kind_name(&old.kind), field_kind_name(&new.kind));
+    if old_kind != new_kind {
+        changes.push(Change::new(
+            path,
+            ChangeKind::ChangedFieldType,
+            Some(old_kind.to_string()),
+            Some(new_kind.to_string()),
+        ));
+        return Ok(());
+    }
+
+    // the size of a bit-field in bytes also depends on its offset, only its width matters
+    match (&old.kind, &new.kind) {
+        (
+            FieldKind::BitField {
+                bit_width: old_width,
+                ..
+            },
+            FieldKind::BitField {
+                bit_width: new_width,
+                ..
+            },
+        ) => push_if_changed(
+            changes,
+            path,
+            ChangeKind::ResizedField,
+            format!("{old_width} bits"),
+            format!("{new_width} bits"),
+        ),
+        _ => push_if_changed(
+            changes,
+            path,
+            ChangeKind::ResizedField,
+            format!("{} bytes", old.size_bytes()?),
+            format!("{} bytes", new.size_bytes()?),
+        ),
+    }
+
+    if let (Some(old_dimensions), Some(new_dimensions)) =
+        (field_dimensions(&old.kind), field_dimensions(&new.kind))
+    {
+        push_if_changed(
+            changes,
+            path,
+            ChangeKind::ChangedFieldType,
+            format!("dimensions {old_dimensions:?}"),
+            format!("dimensions {new_dimensions:?}"),
+        );
+    }
+
+    match (field_element_repr(old), field_element_repr(new)) {
+        (
+            Some(RepresentableCType::Integer {
+                is_unsigned: old_unsigned,
+                ..
+            }),
+            Some(RepresentableCType::Integer {
+                is_unsigned: new_unsigned,
+                ..
+            }),
+        ) => push_if_changed(
+            changes,
+            path,
+            ChangeKind::ChangedSignedness,
+            signedness(old_unsigned),
+            signedness(new_unsigned),
+        ),
+        (Some(old_element), Some(new_element))
+            if std::mem::discriminant(&old_element) != std::mem::discriminant(&new_element) =>
+        {
+            changes.push(Change::new(
+                path,
+                ChangeKind::ChangedFieldType,
+                Some(old_element.to_string()),
+                Some(new_element.to_string()),
+            ))
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn evolve_enum(changes: &mut Vec<Change>, old: &EnumLayout, new: &EnumLayout) {
+    if old.repr != new.repr {
+        changes.push(Change::new(
+            &[],
+            ChangeKind::ChangedEnumRepresentation,
+            Some(old.repr.to_string()),
+            Some(new.repr.to_string()),
+        ));
+    }
+
+    for old_enumerator in &old.enumerators {
+        let path = [old_enumerator.name.clone()];
+        match new
+            .enumerators
+            .iter()
+            .find(|e| e.name == old_enumerator.name)
+        {
+            Some(new_enumerator) => push_if_changed(
+                changes,
+                &path,
+                ChangeKind::ChangedEnumeratorValue,
+                old_enumerator.signed_value,
+                new_enumerator.signed_value,
+            ),
+            None => changes.push(Change::new(
+                &path,
+                ChangeKind::RemovedEnumerator,
+                Some(old_enumerator.signed_value.to_string()),
+                None,
+            )),
+        }
+    }
+
+    for new_enumerator in &new.enumerators {
+        if !old
+            .enumerators
+            .iter()
+            .any(|e| e.name == new_enumerator.name)
+        {
+            let path = [new_enumerator.name.clone()];
+            changes.push(Change::new(
+                &path,
+                ChangeKind::AddedEnumerator,
+                None,
+                Some(new_enumerator.signed_value.to_string()),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChangeKind, EvolutionReport, Verdict};
+    use crate::code_gen::{
+        Endianness, FieldKind, FieldLayout, LensLayout, RecordKind, RecordLayout,
+        RepresentableCType, ScalarMeaning, TargetLayout, TypeLayout,
+    };
+
+    fn int_field(name: &str, offset_bits: u64, bytes: u8, is_unsigned: bool) -> FieldLayout {
+        FieldLayout {
+            path: vec![name.to_owned()],
+            offset_bits,
+            align_bytes: bytes.into(),
+            kind: FieldKind::Scalar {
+                repr: RepresentableCType::Integer { bytes, is_unsigned },
+                meaning: ScalarMeaning::Value,
+            },
+        }
+    }
+
+    fn layout(size_bytes: u64, fields: Vec<FieldLayout>) -> LensLayout {
+        LensLayout {
+            target: TargetLayout {
+                triple: "wasm32-unknown-none".to_owned(),
+                endianness: Endianness::Little,
+                pointer_width_bytes: 4,
+            },
+            types: vec![TypeLayout::Record(RecordLayout {
+                name: "Message".to_owned(),
+                c_type: "Message".to_owned(),
+                kind: RecordKind::Struct,
+                size_bytes,
+                align_bytes: 4,
+                fields,
+            })],
+            constants: vec![],
+            anonymous_enums: vec![],
+        }
+    }
+
+    fn change_kinds(old: &LensLayout, new: &LensLayout) -> (Verdict, Vec<ChangeKind>) {
+        let report = EvolutionReport::new(old, new).unwrap();
+        let type_evolution = &report.types[0];
+        assert_eq!(
+            report.compatible,
+            type_evolution.verdict != Verdict::Breaking
+        );
+        (
+            type_evolution.verdict,
+            type_evolution.changes.iter().map(|c| c.kind).collect(),
+        )
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let old = layout(
+            8,
+            vec![int_field("a", 0, 4, false), int_field("b", 32, 4, false)],
+        );
+        assert_eq!(change_kinds(&old, &old), (Verdict::Unchanged, vec![]));
+    }
+
+    #[test]
+    fn test_appended_and_renamed_fields_are_compatible() {
+        let old = layout(
+            8,
+            vec![int_field("a", 0, 4, false), int_field("b", 32, 4, false)],
+        );
+        let new = layout(
+            12,
+            vec![
+                int_field("a", 0, 4, false),
+                int_field("renamed", 32, 4, false),
+                int_field("c", 64, 4, false),
+            ],
+        );
+        assert_eq!(
+            change_kinds(&old, &new),
+            (
+                Verdict::Compatible,
+                vec![
+                    ChangeKind::GrownRecord,
+                    ChangeKind::RenamedField,
+                    ChangeKind::AppendedField
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_moved_resized_and_resigned_fields_are_breaking() {
+        let old = layout(
+            8,
+            vec![int_field("a", 0, 4, false), int_field("b", 32, 4, false)],
+        );
+
+        let moved = layout(
+            12,
+            vec![int_field("a", 0, 4, false), int_field("b", 64, 4, false)],
+        );
+        assert_eq!(
+            change_kinds(&old, &moved),
+            (
+                Verdict::Breaking,
+                vec![ChangeKind::GrownRecord, ChangeKind::MovedField]
+            )
+        );
+
+        let resized = layout(
+            8,
+            vec![int_field("a", 0, 2, false), int_field("b", 32, 4, false)],
+        );
+        assert_eq!(
+            change_kinds(&old, &resized),
+            (Verdict::Breaking, vec![ChangeKind::ResizedField])
+        );
+
+        let resigned = layout(
+            8,
+            vec![int_field("a", 0, 4, true), int_field("b", 32, 4, false)],
+        );
+        assert_eq!(
+            change_kinds(&old, &resigned),
+            (Verdict::Breaking, vec![ChangeKind::ChangedSignedness])
+        );
+    }
+}
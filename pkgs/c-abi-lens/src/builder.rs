@@ -0,0 +1,1104 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use color_eyre::{Result, eyre::bail};
+use log::{debug, error, info, warn};
+
+use crate::clang_args::{is_path_flag, split_path_arg};
+use crate::code_gen::{
+    self, CInclude, CSection, CSnippet, EmitterConfig, Endianness, JSON_SCHEMA_VERSION,
+    LayoutDocument, LensLayout, RustEmitterConfig, TypeFilter, TypeLayout, compile_patterns,
+    generate_rust_code, generate_wasm_module, generate_wat_module, insert_anonymous_enum_constants,
+    insert_enum_functions, insert_macro_constants, insert_static_asserts, insert_struct_functions,
+    self_test_function,
+};
+use crate::report::{Coverage, GenerationReport, SkippedItem, SkippedKind};
+use crate::{ClangDiagnostic, ClangSession, ParsedFile, expand_response_files};
+
+const TOOL_NAME: &str = env!("CARGO_PKG_NAME");
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The kinds of output this tool can generate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    /// Header-only C library
+    #[default]
+    C,
+
+    /// Rust module
+    Rust,
+
+    /// JSON layout document, following a versioned schema
+    Json,
+
+    /// WebAssembly text format module exporting the selected macros as globals
+    Wat,
+
+    /// Binary WebAssembly module exporting the selected macros as globals
+    Wasm,
+}
+
+/// When to swap the endianness of values in the generated C code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum EndiannessSwap {
+    /// Swap if host and foreign target differ in byte order
+    #[default]
+    Auto,
+
+    /// Always swap
+    Always,
+
+    /// Never swap
+    Never,
+}
+
+/// Configuration of the code generation for one input file
+///
+/// Each option corresponds to the command line option of the same name, see `c-abi-lens --help`.
+///
+/// ```no_run
+/// use c_abi_lens::{Builder, ClangSession, OutputFormat};
+///
+/// let session = ClangSession::new()?;
+/// Builder::new("include/ARINC653-wasm.h")
+///     .clang_args(c_abi_lens::clang_args_from_env()?)
+///     .clang_arg("--target=wasm32-unknown-none")
+///     .prefix("apex")
+///     .output_format(OutputFormat::Rust)
+///     .comment(true)
+///     .write_to(&session, "src/apex.rs")?;
+/// # Ok::<(), color_eyre::Report>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Builder {
+    input_file: PathBuf,
+    clang_args: Vec<String>,
+    prefix: String,
+    function_decl_prefix: Option<String>,
+    comment: bool,
+    only_prototype: bool,
+    deref_helpers: bool,
+    include_types: Vec<String>,
+    exclude_types: Vec<String>,
+    exclude_fields: Vec<String>,
+    main_file_only: bool,
+    allowed_headers: Vec<String>,
+    macros: Vec<String>,
+    output_format: OutputFormat,
+    endianness_swap: EndiannessSwap,
+    host_target: Option<String>,
+    relative_to: Option<PathBuf>,
+}
+
+impl Builder {
+    /// Start the configuration for a `.c` or `.h` input file, with all options at their default
+    pub fn new(input_file: impl Into<PathBuf>) -> Self {
+        Self {
+            input_file: input_file.into(),
+            clang_args: Vec::new(),
+            prefix: "cal".to_owned(),
+            function_decl_prefix: None,
+            comment: false,
+            only_prototype: false,
+            deref_helpers: false,
+            include_types: Vec::new(),
+            exclude_types: Vec::new(),
+            exclude_fields: Vec::new(),
+            main_file_only: false,
+            allowed_headers: Vec::new(),
+            macros: Vec::new(),
+            output_format: OutputFormat::default(),
+            endianness_swap: EndiannessSwap::default(),
+            host_target: None,
+            relative_to: None,
+        }
+    }
+
+    /// Replace the input file, keeping all options
+    pub fn input_file(mut self, input_file: impl Into<PathBuf>) -> Self {
+        self.input_file = input_file.into();
+        self
+    }
+
+    /// Append one argument passed verbatim to libclang, e.g. `--target=wasm32-unknown-none`
+    ///
+    /// An argument `@<FILE>` is replaced by the shell words of that response file.
+    pub fn clang_arg(mut self, clang_arg: impl Into<String>) -> Self {
+        self.clang_args.push(clang_arg.into());
+        self
+    }
+
+    /// Append arguments passed verbatim to libclang
+    pub fn clang_args<I: IntoIterator<Item = S>, S: Into<String>>(mut self, clang_args: I) -> Self {
+        self.clang_args
+            .extend(clang_args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Prefix used before all visible identifiers, `cal` by default
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Prefix for each C function declaration, e.g. `static inline`
+    pub fn function_decl_prefix(mut self, function_decl_prefix: impl Into<String>) -> Self {
+        self.function_decl_prefix = Some(function_decl_prefix.into());
+        self
+    }
+
+    /// Emit a doc-comment for each function
+    pub fn comment(mut self, comment: bool) -> Self {
+        self.comment = comment;
+        self
+    }
+
+    /// Only emit a prototype for each C function, without its body
+    pub fn only_prototype(mut self, only_prototype: bool) -> Self {
+        self.only_prototype = only_prototype;
+        self
+    }
+
+    /// Emit a dereference helper for each pointer field
+    pub fn deref_helpers(mut self, deref_helpers: bool) -> Self {
+        self.deref_helpers = deref_helpers;
+        self
+    }
+
+    /// Only consider types with a name matching this regex, may be given multiple times
+    pub fn include_type(mut self, pattern: impl Into<String>) -> Self {
+        self.include_types.push(pattern.into());
+        self
+    }
+
+    /// Skip types with a name matching this regex, may be given multiple times
+    pub fn exclude_type(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude_types.push(pattern.into());
+        self
+    }
+
+    /// Skip fields with a `type.field` name matching this regex, may be given multiple times
+    pub fn exclude_field(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude_fields.push(pattern.into());
+        self
+    }
+
+    /// Only consider types declared in the input file itself, or in an allowed header
+    pub fn main_file_only(mut self, main_file_only: bool) -> Self {
+        self.main_file_only = main_file_only;
+        self
+    }
+
+    /// Additionally consider types declared in headers with a path matching this regex
+    pub fn allow_header(mut self, pattern: impl Into<String>) -> Self {
+        self.allowed_headers.push(pattern.into());
+        self
+    }
+
+    /// Export object-like macros with a name matching this regex as constants
+    pub fn macro_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.macros.push(pattern.into());
+        self
+    }
+
+    /// The kind of output to generate, a header-only C library by default
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    /// When to swap the endianness of values in the generated C code, automatically by default
+    pub fn endianness_swap(mut self, endianness_swap: EndiannessSwap) -> Self {
+        self.endianness_swap = endianness_swap;
+        self
+    }
+
+    /// Target triple of the host the generated C code runs on, the current host by default
+    pub fn host_target(mut self, host_target: impl Into<String>) -> Self {
+        self.host_target = Some(host_target.into());
+        self
+    }
+
+    /// Record paths in the generated output relative to `directory`, rather than as given
+    ///
+    /// Applies to the input file and to the paths under `directory` passed to include and sysroot
+    /// flags within the clang args, e.g. `-I<directory>/include`. This keeps the output the same
+    /// across checkouts in different places.
+    pub fn relative_to(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.relative_to = Some(directory.into());
+        self
+    }
+
+    /// The input file and the clang args as recorded in the generated output
+    fn recorded_paths(&self) -> Result<(PathBuf, Vec<String>)> {
+        let Some(directory) = &self.relative_to else {
+            return Ok((self.input_file.clone(), self.clang_args.clone()));
+        };
+        let directory = std::path::absolute(directory)?;
+
+        let input_file = relative_path(&std::path::absolute(&self.input_file)?, &directory);
+
+        // only the paths under `directory` are recorded relative to it, others as given
+        let relative = |path: &str| match Path::new(path).starts_with(&directory) {
+            true => relative_path(Path::new(path), &directory)
+                .to_string_lossy()
+                .into_owned(),
+            false => path.to_owned(),
+        };
+
+        let mut clang_args = Vec::new();
+        let mut args = self.clang_args.iter();
+        while let Some(arg) = args.next() {
+            if is_path_flag(arg) {
+                clang_args.push(arg.clone());
+                clang_args.extend(args.next().map(|path| relative(path)));
+            } else if let Some((flag, path)) = split_path_arg(arg) {
+                clang_args.push(format!("{flag}{}", relative(path)));
+            } else {
+                clang_args.push(arg.clone());
+            }
+        }
+
+        Ok((input_file, clang_args))
+    }
+
+    /// Parse the input file and gather the layout of all selected types
+    pub fn lens(&self, session: &ClangSession) -> Result<Lens> {
+        let type_filter = TypeFilter::new(
+            &self.include_types,
+            &self.exclude_types,
+            &self.exclude_fields,
+            self.main_file_only,
+            &self.allowed_headers,
+        )?;
+        let macro_patterns = compile_patterns(&self.macros)?;
+
+        // record the args actually passed to clang, rather than the response files
+        let mut config = self.clone();
+        config.clang_args = expand_response_files(&self.clang_args)?;
+
+        let parsed_file = session.parse_layout(
+            &config.input_file,
+            &config.clang_args,
+            &type_filter,
+            &macro_patterns,
+        )?;
+
+        Lens::new(config, parsed_file)
+    }
+
+    /// Generate the output as a string, see [`Lens::generate`]
+    pub fn generate(&self, session: &ClangSession) -> Result<String> {
+        self.lens(session)?.generate()
+    }
+
+    /// Generate the output and write it to a file, see [`Lens::write_to`]
+    pub fn write_to(&self, session: &ClangSession, output_file: impl AsRef<Path>) -> Result<()> {
+        self.lens(session)?.write_to(output_file)
+    }
+}
+
+/// The layout of all types selected by a [`Builder`], from which any output can be generated
+pub struct Lens {
+    config: Builder,
+    layout: LensLayout,
+    diagnostics: Vec<ClangDiagnostic>,
+    included_files: Vec<PathBuf>,
+    coverage: Coverage,
+    swap_endianness: bool,
+    options_summary: String,
+
+    /// The input file and clang args as recorded in the output, see [`Builder::relative_to`]
+    input_file: PathBuf,
+    clang_args: Vec<String>,
+}
+
+impl Lens {
+    fn new(
+        config: Builder,
+        ParsedFile {
+            layout,
+            diagnostics,
+            included_files,
+            coverage,
+        }: ParsedFile,
+    ) -> Result<Self> {
+        let (input_file, clang_args) = config.recorded_paths()?;
+        let Builder {
+            prefix,
+            function_decl_prefix,
+            comment,
+            only_prototype,
+            deref_helpers,
+            include_types,
+            exclude_types,
+            exclude_fields,
+            main_file_only,
+            allowed_headers,
+            macros,
+            output_format,
+            endianness_swap,
+            host_target,
+            ..
+        } = &config;
+        let target_triple = &layout.target.triple;
+
+        // swap bytes exactly if host and foreign target disagree on the byte order, unless overridden
+        let host_endianness = host_target
+            .as_deref()
+            .map(Endianness::of_target_triple)
+            .unwrap_or_else(Endianness::native);
+        let target_endianness = layout.target.endianness;
+        let detected_swap = host_endianness != target_endianness;
+        let swap_endianness = match endianness_swap {
+            EndiannessSwap::Auto => detected_swap,
+            EndiannessSwap::Always => true,
+            EndiannessSwap::Never => false,
+        };
+        if swap_endianness != detected_swap {
+            warn!(
+                "--endianness-swap {endianness_swap:?} contradicts the byte order of the host ({host_endianness:?}) and of {target_triple} ({target_endianness:?})"
+            );
+        }
+
+        // options which shaped the generated code
+        let options_summary = format!(
+            "\
+            input_file ........... {input_file:?}\n\
+            clang_args ........... {clang_args:?}\n\
+            target_arch .......... {target_triple}\n\
+            host_endianness ...... {host_endianness:?}\n\
+            endianness_swap ...... {swap_endianness}\n\
+            output_format ........ {output_format:?}\n\
+            prefix ............... {prefix}\n\
+            function_decl_prefix . {function_decl_prefix:?}\n\
+            emit_comment ......... {comment}\n\
+            only_prototype........ {only_prototype}\n\
+            deref_helpers ........ {deref_helpers}\n\
+            include_type ......... {include_types:?}\n\
+            exclude_type ......... {exclude_types:?}\n\
+            exclude_field ........ {exclude_fields:?}\n\
+            main_file_only ....... {main_file_only}\n\
+            allow_header ......... {allowed_headers:?}\n\
+            macros ............... {macros:?}\n\
+            generated_by ......... {TOOL_NAME} v{TOOL_VERSION}\
+            "
+        );
+
+        Ok(Self {
+            config,
+            layout,
+            diagnostics,
+            included_files,
+            coverage,
+            swap_endianness,
+            options_summary,
+            input_file,
+            clang_args,
+        })
+    }
+
+    /// The layout of all selected types, as well as the value of all selected macros
+    pub fn layout(&self) -> &LensLayout {
+        &self.layout
+    }
+
+    /// The diagnostics libclang reported while parsing the input file
+    pub fn diagnostics(&self) -> &[ClangDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// The input file followed by every file it includes, i.e. all files the output depends on
+    pub fn included_files(&self) -> &[PathBuf] {
+        &self.included_files
+    }
+
+    /// Generate a Makefile-compatible dependency file, stating that the `targets` depend on all
+    /// [included files](Self::included_files)
+    ///
+    /// Each included file additionally gets an empty rule, so that make does not fail once a header
+    /// is deleted, just as with `gcc -MP`.
+    pub fn depfile<P: AsRef<Path>>(&self, targets: &[P]) -> String {
+        generate_depfile(targets, &self.included_files)
+    }
+
+    /// Whether the generated C code swaps the endianness of values
+    pub fn swaps_endianness(&self) -> bool {
+        self.swap_endianness
+    }
+
+    fn emitter_config(&self) -> EmitterConfig {
+        EmitterConfig {
+            swap_endianness: self.swap_endianness,
+            emit_deref_helpers: self.config.deref_helpers,
+        }
+    }
+
+    /// Generate the output in the configured [`OutputFormat`] as a string
+    ///
+    /// Fails for [`OutputFormat::Wasm`], as a binary module is no string, use
+    /// [`Lens::generate_bytes`] instead.
+    pub fn generate(&self) -> Result<String> {
+        self.generate_covered(&mut self.coverage.clone())
+    }
+
+    /// Summarize which types and fields the output in the configured [`OutputFormat`] covers, and
+    /// which were skipped
+    ///
+    /// Generates the output to do so, use [`Lens::generate_with_report`] if the output is needed too.
+    pub fn report(&self) -> Result<GenerationReport> {
+        Ok(self.generate_with_report()?.1)
+    }
+
+    /// Generate the output in the configured [`OutputFormat`] as bytes, together with the
+    /// [report](Self::report) on what it covers
+    pub fn generate_with_report(&self) -> Result<(Vec<u8>, GenerationReport)> {
+        let mut coverage = self.coverage.clone();
+        let code = self.generate_bytes_covered(&mut coverage)?;
+        let report = GenerationReport::new(self.input_file.clone(), &self.layout, &coverage);
+        Ok((code, report))
+    }
+
+    /// Generate the output in the configured [`OutputFormat`] as bytes and the
+    /// [self-test program](Self::self_test), together with the [report](Self::report) on what
+    /// both cover
+    pub fn generate_with_self_test_and_report(
+        &self,
+    ) -> Result<(Vec<u8>, String, GenerationReport)> {
+        let mut coverage = self.coverage.clone();
+        let code = self.generate_bytes_covered(&mut coverage)?;
+        let self_test = self.self_test_covered(&mut coverage);
+        let report = GenerationReport::new(self.input_file.clone(), &self.layout, &coverage);
+        Ok((code, self_test, report))
+    }
+
+    /// Generate the output as string, recording skipped items in the `coverage`
+    fn generate_covered(&self, coverage: &mut Coverage) -> Result<String> {
+        let Builder {
+            prefix,
+            function_decl_prefix,
+            comment,
+            only_prototype,
+            deref_helpers,
+            output_format,
+            endianness_swap,
+            ..
+        } = &self.config;
+        let layout = &self.layout;
+        let options_summary = &self.options_summary;
+
+        if *output_format != OutputFormat::C
+            && (*endianness_swap != EndiannessSwap::Auto
+                || *only_prototype
+                || function_decl_prefix.is_some())
+        {
+            warn!(
+                "--endianness-swap, --only-prototype and --function-decl-prefix only apply to C output, ignoring them"
+            );
+        }
+
+        if matches!(output_format, OutputFormat::Wat | OutputFormat::Wasm)
+            && layout.constants.is_empty()
+        {
+            warn!("no macro selected via --macro, the WebAssembly module exports nothing");
+        }
+
+        let code = match output_format {
+            OutputFormat::C => {
+                let (code, skipped) = generate_c_code(
+                    layout,
+                    &self.emitter_config(),
+                    options_summary,
+                    prefix,
+                    *comment,
+                    *only_prototype,
+                    function_decl_prefix.as_deref(),
+                );
+                coverage.skipped.extend(skipped);
+                code
+            }
+            OutputFormat::Rust => {
+                let header = format!(
+                    "\
+                    THIS CODE IS AUTOGENERATED, DON'T MODIFY BY HAND\n\
+                    \n\
+                    This module provides access to data type instances represented in a foreign\n\
+                    architecture's ABI, stored in a byte slice. In particular, it provides getter and\n\
+                    setter functions for struct and union fields which observe the foreign\n\
+                    architecture's memory layout, as well as the foreign values of enumerators.\n\
+                    \n\
+                    {options_summary}\
+                    "
+                );
+                let emitter_config = RustEmitterConfig {
+                    module_name: prefix.clone(),
+                    emit_comment: *comment,
+                    emit_deref_helpers: *deref_helpers,
+                };
+                debug!("emitter config: {emitter_config:?}");
+                generate_rust_code(layout, &header, &emitter_config, coverage)
+            }
+            OutputFormat::Json => LayoutDocument {
+                schema_version: JSON_SCHEMA_VERSION,
+                generated_by: format!("{TOOL_NAME} v{TOOL_VERSION}"),
+                input_file: self.input_file.clone(),
+                clang_args: self.clang_args.clone(),
+                layout: layout.clone(),
+            }
+            .to_json()?,
+            OutputFormat::Wat => {
+                let header = format!(
+                    "\
+                    THIS CODE IS AUTOGENERATED, DON'T MODIFY BY HAND\n\
+                    \n\
+                    This module exports the values of preprocessor macros on a foreign architecture\n\
+                    as immutable globals.\n\
+                    \n\
+                    {options_summary}\
+                    "
+                );
+                generate_wat_module(&layout.constants, &header)?
+            }
+            OutputFormat::Wasm => {
+                bail!("a binary WebAssembly module can not be generated as string")
+            }
+        };
+
+        debug!("done generating code");
+        Ok(code)
+    }
+
+    /// Generate the output in the configured [`OutputFormat`] as bytes
+    pub fn generate_bytes(&self) -> Result<Vec<u8>> {
+        self.generate_bytes_covered(&mut self.coverage.clone())
+    }
+
+    /// Generate the output as bytes, recording skipped items in the `coverage`
+    fn generate_bytes_covered(&self, coverage: &mut Coverage) -> Result<Vec<u8>> {
+        match self.config.output_format {
+            OutputFormat::Wasm => generate_wasm_module(&self.layout.constants),
+            _ => Ok(self.generate_covered(coverage)?.into_bytes()),
+        }
+    }
+
+    /// Generate the output in the configured [`OutputFormat`] and write it to a file
+    pub fn write_to(&self, output_file: impl AsRef<Path>) -> Result<()> {
+        let output_file = output_file.as_ref();
+        let code = self.generate_bytes()?;
+        info!("writing output to {output_file:?}");
+        File::create(output_file)?.write_all(&code)?;
+        Ok(())
+    }
+
+    /// Generate the output in the configured [`OutputFormat`] and compare it against the contents of
+    /// a file, see [`check_file`]
+    pub fn check(&self, output_file: impl AsRef<Path>) -> Result<Option<String>> {
+        check_file(output_file, &self.generate_bytes()?)
+    }
+
+    /// Generate the companion C header with static assertions on the layout
+    pub fn static_asserts(&self) -> String {
+        debug!("generating static assertions");
+        generate_static_asserts(
+            &self.layout,
+            &self.options_summary,
+            &self.config.input_file,
+            self.config.comment,
+        )
+    }
+
+    /// Generate a standalone C program testing the accessors of the header-only C library
+    pub fn self_test(&self) -> String {
+        self.self_test_covered(&mut self.coverage.clone())
+    }
+
+    /// Generate the self-test program, recording types without a self-test in the `coverage`
+    fn self_test_covered(&self, coverage: &mut Coverage) -> String {
+        debug!("generating self-test program");
+        generate_self_test(
+            &self.layout,
+            &self.emitter_config(),
+            &self.options_summary,
+            &self.config.prefix,
+            self.config.comment,
+            self.config.function_decl_prefix.as_deref(),
+            coverage,
+        )
+    }
+}
+
+/// Generate the header-only C library for a given layout, as well as the items it skips
+fn generate_c_code(
+    layout: &LensLayout,
+    emitter_config: &EmitterConfig,
+    options_summary: &str,
+    prefix: &str,
+    comment: bool,
+    only_prototype: bool,
+    function_decl_prefix: Option<&str>,
+) -> (String, Vec<SkippedItem>) {
+    let mut code_snippets = Vec::new();
+    let mut coverage = Coverage::default();
+
+    // section header for the entire library
+    code_snippets.push(
+        CSection {
+            title: " c-abi-lens ".to_owned(),
+            comment: format!(
+                "\
+                THIS CODE IS AUTOGENERATED, DON'T MODIFY BY HAND\n\
+                \n\
+                This header-only library provides access to data type instances represented in\n\
+                a foreign architecture's ABI. In particular, it provides getter and setter\n\
+                functions for struct and union fields which observe the foreign architecture's\n\
+                memory layout, as well as the foreign values of enumerators.\n\
+                \n\
+                {options_summary}\
+                "
+            ),
+        }
+        .into(),
+    );
+
+    code_snippets.push(CSnippet::Newline);
+
+    // add include of `stdint.h` and `string.h`
+    code_snippets.push(CInclude::System("stdint.h".to_owned()).into());
+    code_snippets.push(CInclude::System("string.h".to_owned()).into());
+
+    // if endianness swap is desired, include the header file for it
+    if emitter_config.swap_endianness {
+        code_snippets.push(CInclude::System("byteswap.h".to_owned()).into());
+    }
+
+    code_snippets.push(CSnippet::Newline);
+    code_snippets.push(CSnippet::Newline);
+
+    debug!("emitter config: {emitter_config:?}");
+
+    // constants for the selected macros
+    insert_macro_constants(&mut code_snippets, &layout.constants);
+
+    // constants for the enumerators of anonymous enums
+    if let Err(e) = insert_anonymous_enum_constants(&mut code_snippets, &layout.anonymous_enums) {
+        error!(
+            "skipping the anonymous enums, because the following error occured while generating them:\n{e}"
+        );
+    }
+
+    // Print information about the structs and enums
+    for type_layout in &layout.types {
+        let (result, name) = match type_layout {
+            TypeLayout::Enum(enum_) => (
+                insert_enum_functions(&mut code_snippets, enum_),
+                &enum_.name,
+            ),
+            TypeLayout::Record(record) => (
+                insert_struct_functions(
+                    &mut code_snippets,
+                    record,
+                    &layout.target,
+                    emitter_config,
+                    &mut coverage,
+                ),
+                &record.name,
+            ),
+        };
+
+        if let Err(e) = result {
+            error!(
+                "skipping to the next type, because the following error occured while generating its functions:\n{e}"
+            );
+            coverage.skip(SkippedKind::Type, name, format!("{e:#}"), None);
+        }
+    }
+
+    code_snippets.push(CSnippet::Newline);
+
+    // apply prefix to all function and macro names, if its not an empty string
+    if !prefix.is_empty() {
+        debug!("applying function and macro prefixes");
+        for snippet in &mut code_snippets {
+            if let CSnippet::Func(code_gen::CFunc { name, .. })
+            | CSnippet::Define(code_gen::CDefine { name, .. }) = snippet
+            {
+                *name = format!("{prefix}_{name}")
+            }
+        }
+    }
+
+    let code = assemble_c_code(
+        &code_snippets,
+        comment,
+        only_prototype,
+        function_decl_prefix,
+    );
+    (code, coverage.skipped)
+}
+
+/// Generate the companion C header with static assertions on the layout
+fn generate_static_asserts(
+    layout: &LensLayout,
+    options_summary: &str,
+    input_file: &Path,
+    comment: bool,
+) -> String {
+    let mut code_snippets = Vec::new();
+
+    // section header for the entire header
+    code_snippets.push(
+        CSection {
+            title: " c-abi-lens static assertions ".to_owned(),
+            comment: format!(
+                "\
+                THIS CODE IS AUTOGENERATED, DON'T MODIFY BY HAND\n\
+                \n\
+                This header asserts that the size of each struct and union and the offset of each\n\
+                of their fields matches the layout assumed by the code generated alongside it.\n\
+                Include it in the build for the foreign target, after the declarations of the\n\
+                types it refers to.\n\
+                \n\
+                {options_summary}\
+                "
+            ),
+        }
+        .into(),
+    );
+
+    code_snippets.push(CSnippet::Newline);
+
+    // add include of `stddef.h` for `offsetof`, and of the input file if it is a header
+    code_snippets.push(CInclude::System("stddef.h".to_owned()).into());
+    if input_file.extension().is_some_and(|e| e == "h")
+        && let Some(file_name) = input_file.file_name()
+    {
+        code_snippets.push(CInclude::Library(file_name.to_string_lossy().into_owned()).into());
+    }
+
+    code_snippets.push(CSnippet::Newline);
+    code_snippets.push(CSnippet::Newline);
+
+    for type_layout in &layout.types {
+        if let TypeLayout::Record(record) = type_layout {
+            insert_static_asserts(&mut code_snippets, record);
+        }
+    }
+
+    assemble_c_code(&code_snippets, comment, false, None)
+}
+
+/// Generate a standalone C program testing the accessors of the header-only C library
+///
+/// Types for which no self-test can be generated are skipped, and recorded in the `coverage`.
+fn generate_self_test(
+    layout: &LensLayout,
+    emitter_config: &EmitterConfig,
+    options_summary: &str,
+    prefix: &str,
+    comment: bool,
+    function_decl_prefix: Option<&str>,
+    coverage: &mut Coverage,
+) -> String {
+    // the library under test, always with function bodies
+    let library = generate_c_code(
+        layout,
+        emitter_config,
+        options_summary,
+        prefix,
+        comment,
+        false,
+        function_decl_prefix,
+    )
+    .0;
+
+    let mut code_snippets = vec![
+        // section header for the self-test
+        CSection {
+            title: " c-abi-lens self-test ".to_owned(),
+            comment: "\
+                Round trip tests for the accessors above, run on the host.\n\
+                \n\
+                The program exits with EXIT_FAILURE if any check fails.\
+                "
+            .to_owned(),
+        }
+        .into(),
+        CSnippet::Newline,
+        // add include of `stdio.h` for reporting and `stdlib.h` for the exit codes
+        CInclude::System("stdio.h".to_owned()).into(),
+        CInclude::System("stdlib.h".to_owned()).into(),
+        CSnippet::Newline,
+        CSnippet::Newline,
+    ];
+
+    let mut test_function_names = Vec::new();
+    for type_layout in &layout.types {
+        let TypeLayout::Record(record) = type_layout else {
+            continue;
+        };
+
+        match self_test_function(record, prefix) {
+            Ok(Some(test_function)) => {
+                test_function_names.push(test_function.name.clone());
+                code_snippets.push(test_function.into());
+                code_snippets.push(CSnippet::Newline);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!(
+                    "skipping to the next type, because the following error occured while generating its self-test:\n{e}"
+                );
+                coverage.skip(SkippedKind::Type, &record.name, format!("{e:#}"), None);
+            }
+        }
+    }
+
+    let tests = assemble_c_code(&code_snippets, comment, false, None);
+
+    // the entry point running all tests
+    let test_calls: String = test_function_names
+        .iter()
+        .map(|name| format!("    failures += {name}();\n"))
+        .collect();
+    let main_function = format!(
+        "\
+        int main(void)\n\
+        {{\n\
+        \x20   unsigned long failures = 0;\n\
+        {test_calls}\
+        \n\
+        \x20   if (failures != 0) {{\n\
+        \x20       fprintf(stderr, \"%lu checks failed\\n\", failures);\n\
+        \x20       return EXIT_FAILURE;\n\
+        \x20   }}\n\
+        \n\
+        \x20   printf(\"all checks passed\\n\");\n\
+        \x20   return EXIT_SUCCESS;\n\
+        }}\n\
+        "
+    );
+
+    [library, tests, main_function].join("\n")
+}
+
+/// Turn the snippets into the final C code
+fn assemble_c_code(
+    code_snippets: &[CSnippet],
+    comment: bool,
+    only_prototype: bool,
+    function_decl_prefix: Option<&str>,
+) -> String {
+    // assmeble the code
+    let section_title_comment_width = 80;
+    let spaces_per_tab = 4;
+
+    // actually generate strings
+    let mut code_snippet_strings: Vec<String> = code_snippets
+        .iter()
+        .map(|s| match s {
+            CSnippet::Include(cinclude) => cinclude.generate(),
+            CSnippet::Define(cdefine) => cdefine.generate(comment),
+            CSnippet::Func(cfunc) => cfunc.generate(comment, !only_prototype, function_decl_prefix),
+            CSnippet::StaticAssert(cstatic_assert) => cstatic_assert.generate(comment),
+            CSnippet::Section(csection) => csection.generate(section_title_comment_width),
+            CSnippet::Newline => '\n'.into(),
+        })
+        .collect();
+
+    // retab with spaces
+    for snip in &mut code_snippet_strings {
+        let tab_space_replacement = " ".repeat(spaces_per_tab);
+        *snip = snip.replace('\t', &tab_space_replacement);
+
+        // normalize newline endings
+        if !snip.ends_with("\n") {
+            snip.push('\n');
+        }
+    }
+
+    // join it into a string
+    code_snippet_strings.concat()
+}
+
+/// Generate a Makefile-compatible dependency file, the first dependency is the main one
+fn generate_depfile<P: AsRef<Path>>(targets: &[P], dependencies: &[PathBuf]) -> String {
+    let escape = |path: &Path| {
+        path.to_string_lossy()
+            .replace('$', "$$")
+            .replace('#', "\\#")
+            .replace(' ', "\\ ")
+    };
+
+    let mut depfile = targets
+        .iter()
+        .map(|t| escape(t.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    depfile.push(':');
+    for dependency in dependencies {
+        depfile.push_str(" \\\n  ");
+        depfile.push_str(&escape(dependency));
+    }
+    depfile.push('\n');
+
+    for dependency in dependencies.iter().skip(1) {
+        depfile.push_str(&format!("\n{}:\n", escape(dependency)));
+    }
+
+    depfile
+}
+
+/// Compare the `expected` contents against those of an existing file, e.g. a committed lens
+///
+/// Returns `None` if they are identical, otherwise a unified diff from the file's contents to the
+/// `expected` ones. A file that does not exist is treated as empty. Binary contents are not diffed,
+/// but merely reported as different.
+pub fn check_file(path: impl AsRef<Path>, expected: &[u8]) -> Result<Option<String>> {
+    let path = path.as_ref();
+    debug!("checking {path:?}");
+    let actual = match std::fs::read(path) {
+        Ok(actual) => actual,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => bail!("unable to read {path:?}: {e}"),
+    };
+    if actual == expected {
+        return Ok(None);
+    }
+
+    let (Ok(actual), Ok(expected)) = (str::from_utf8(&actual), str::from_utf8(expected)) else {
+        return Ok(Some(format!("Binary file {} differs\n", path.display())));
+    };
+    let path = path.display().to_string();
+    let diff = similar::TextDiff::from_lines(actual, expected)
+        .unified_diff()
+        .header(&path, &format!("{path} (generated)"))
+        .to_string();
+    Ok(Some(diff))
+}
+
+/// `path` relative to `base`, both of which have to be absolute
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+    while let (Some(p), Some(b)) = (path_components.peek(), base_components.peek())
+        && p == b
+    {
+        path_components.next();
+        base_components.next();
+    }
+
+    let relative: PathBuf = base_components
+        .map(|_| Component::ParentDir)
+        .chain(path_components)
+        .collect();
+    match relative.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => relative,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{Builder, check_file, generate_depfile, relative_path};
+
+    #[test]
+    fn test_recorded_paths() {
+        let builder = Builder::new("/repo/include/a.h")
+            .clang_args([
+                "-I/repo/include",
+                "-isystem",
+                "/repo/sys",
+                "--sysroot=/repo",
+                "-I/opt/repo/include",
+                "-I/repository",
+                "-DPATH=/repo/x",
+            ])
+            .relative_to("/repo");
+        assert_eq!(
+            builder.recorded_paths().unwrap(),
+            (
+                PathBuf::from("include/a.h"),
+                [
+                    "-Iinclude",
+                    "-isystem",
+                    "sys",
+                    "--sysroot=.",
+                    "-I/opt/repo/include",
+                    "-I/repository",
+                    "-DPATH=/repo/x",
+                ]
+                .map(str::to_owned)
+                .to_vec()
+            )
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let relative = |path, base| relative_path(Path::new(path), Path::new(base));
+        assert_eq!(
+            relative("/repo/include/a.h", "/repo"),
+            Path::new("include/a.h")
+        );
+        assert_eq!(
+            relative("/repo/include/a.h", "/repo/src"),
+            Path::new("../include/a.h")
+        );
+        assert_eq!(
+            relative("/opt/a.h", "/repo/src"),
+            Path::new("../../opt/a.h")
+        );
+        assert_eq!(relative("/repo", "/repo"), Path::new("."));
+    }
+
+    #[test]
+    fn test_depfile() {
+        let depfile = generate_depfile(
+            &["lens.h", "asserts.h"],
+            &[
+                PathBuf::from("include/ARINC653-wasm.h"),
+                PathBuf::from("/opt/my sysroot/include/stdint.h"),
+                PathBuf::from("include/$weird#.h"),
+            ],
+        );
+        assert_eq!(
+            depfile,
+            "\
+            lens.h asserts.h: \\\n\
+            \x20 include/ARINC653-wasm.h \\\n\
+            \x20 /opt/my\\ sysroot/include/stdint.h \\\n\
+            \x20 include/$$weird\\#.h\n\
+            \n\
+            /opt/my\\ sysroot/include/stdint.h:\n\
+            \n\
+            include/$$weird\\#.h:\n\
+            "
+        );
+    }
+
+    #[test]
+    fn test_check_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lens.h");
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+
+        assert_eq!(check_file(&path, b"a\nb\nc\n").unwrap(), None);
+
+        let diff = check_file(&path, b"a\nB\nc\n").unwrap().unwrap();
+        assert!(diff.contains("-b\n+B\n"), "{diff}");
+
+        let missing = dir.path().join("missing.h");
+        let diff = check_file(&missing, b"a\n").unwrap().unwrap();
+        assert!(diff.contains("+a\n"), "{diff}");
+
+        let diff = check_file(&path, &[0xff, 0x00]).unwrap().unwrap();
+        assert!(diff.starts_with("Binary file"), "{diff}");
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+use log::{debug, trace};
+use serde::Deserialize;
+
+/// Maximum nesting of response files, guards against a response file including itself
+const MAX_RESPONSE_FILE_DEPTH: usize = 16;
+
+/// Flags which take a path as separate argument, or appended as described in [`split_path_arg`]
+const PATH_FLAGS: &[&str] = &[
+    "-I",
+    "-isystem",
+    "-iquote",
+    "-idirafter",
+    "-include",
+    "-include-pch",
+    "-imacros",
+    "-isysroot",
+    "--sysroot",
+];
+
+/// Whether `arg` is one of the flags taking a path as separate argument, e.g. `-isystem`
+pub(crate) fn is_path_flag(arg: &str) -> bool {
+    PATH_FLAGS.contains(&arg)
+}
+
+/// Split an argument with a path appended to a flag into the flag and the path
+///
+/// Only single-letter flags take the path directly appended, e.g. `-I<path>`, and only long flags
+/// take it separated by `=`, e.g. `--sysroot=<path>` (with the returned flag being `--sysroot=`).
+/// Other flags merely starting like a path flag, e.g. `-include-pch`, are no match.
+pub(crate) fn split_path_arg(arg: &str) -> Option<(&str, &str)> {
+    PATH_FLAGS.iter().find_map(|flag| {
+        let path = arg.strip_prefix(flag)?;
+        match (flag.len(), flag.starts_with("--")) {
+            (2, _) if !path.is_empty() => Some((*flag, path)),
+            (_, true) if path.starts_with('=') => Some(arg.split_at(flag.len() + 1)),
+            _ => None,
+        }
+    })
+}
+
+/// Get default clang args from environment variables
+///
+/// The variables are split like a POSIX shell would split them, i.e. quoted or escaped arguments
+/// may contain whitespace.
+pub fn clang_args_from_env() -> Result<Vec<String>> {
+    debug!("adding additional clang_args from environment variables");
+    let mut clang_args = Vec::new();
+    for var in ["EXTRA_CLANG_ARGS", "BINDGEN_EXTRA_CLANG_ARGS"] {
+        trace!("reading {var} environment variable");
+        if let Ok(value) = std::env::var(var) {
+            clang_args.extend(
+                split_shell_words(&value).map_err(|e| eyre!("invalid {var} variable: {e}"))?,
+            );
+        }
+    }
+    Ok(clang_args)
+}
+
+/// Split `s` into words, honouring POSIX shell quoting and escaping
+pub fn split_shell_words(s: &str) -> Result<Vec<String>> {
+    shlex::split(s).ok_or_else(|| eyre!("unbalanced quotes or trailing backslash in {s:?}"))
+}
+
+/// Replace each `@<FILE>` argument with the shell words of that response file
+///
+/// Response files may reference further response files, a relative path is relative to the
+/// current working directory.
+pub fn expand_response_files(clang_args: &[String]) -> Result<Vec<String>> {
+    fn expand(clang_args: &[String], depth: usize, expanded: &mut Vec<String>) -> Result<()> {
+        for arg in clang_args {
+            let Some(response_file) = arg.strip_prefix('@') else {
+                expanded.push(arg.clone());
+                continue;
+            };
+            if depth == MAX_RESPONSE_FILE_DEPTH {
+                bail!("response files nested deeper than {MAX_RESPONSE_FILE_DEPTH} levels");
+            }
+
+            debug!("reading clang args from response file {response_file:?}");
+            let content = fs::read_to_string(response_file)
+                .map_err(|e| eyre!("unable to read response file {response_file:?}: {e}"))?;
+            let words = split_shell_words(&content)
+                .map_err(|e| eyre!("invalid response file {response_file:?}: {e}"))?;
+            expand(&words, depth + 1, expanded)?;
+        }
+        Ok(())
+    }
+
+    let mut expanded = Vec::new();
+    expand(clang_args, 0, &mut expanded)?;
+    Ok(expanded)
+}
+
+/// A JSON compilation database, i.e. a `compile_commands.json`
+///
+/// Holds the exact command each source file was compiled with, so that the input file can be
+/// parsed with the same target, defines and include paths as the foreign build.
+#[derive(Debug, Clone)]
+pub struct CompilationDatabase {
+    path: PathBuf,
+    entries: Vec<CompileCommand>,
+}
+
+/// One entry of a [`CompilationDatabase`]
+#[derive(Debug, Clone, Deserialize)]
+struct CompileCommand {
+    /// The working directory of the compilation, all relative paths are relative to it
+    directory: PathBuf,
+
+    /// The source file compiled
+    file: PathBuf,
+
+    /// The compile command as list of arguments, takes precedence over `command`
+    arguments: Option<Vec<String>>,
+
+    /// The compile command as one shell-escaped string
+    command: Option<String>,
+}
+
+impl CompilationDatabase {
+    /// Read the `compile_commands.json` in `directory`
+    ///
+    /// A trailing comma after the last entry is tolerated, as it is left by concatenating the
+    /// fragments written by clang's `-gen-cdb-fragment-path`.
+    pub fn from_directory(directory: impl AsRef<Path>) -> Result<Self> {
+        let path = directory.as_ref().join("compile_commands.json");
+        debug!("reading compilation database {path:?}");
+        let content = fs::read_to_string(&path)
+            .map_err(|e| eyre!("unable to read compilation database {path:?}: {e}"))?;
+
+        let content = content.trim_end();
+        let content = match content.strip_suffix(']') {
+            Some(entries) => format!("{}]", entries.trim_end().trim_end_matches(',')),
+            None => content.to_owned(),
+        };
+        let entries = serde_json::from_str(&content)
+            .map_err(|e| eyre!("invalid compilation database {path:?}: {e}"))?;
+
+        Ok(Self { path, entries })
+    }
+
+    /// The clang args the `source_file` was compiled with
+    ///
+    /// The compiler itself, the source file, the output file and any flags only affecting the
+    /// compiler's outputs or the linker are dropped. Relative include and sysroot paths are made
+    /// absolute, so the args are valid independent of the working directory. If the source file was
+    /// compiled multiple times, e.g. in debug and release mode, the first entry is used.
+    pub fn clang_args_for(&self, source_file: impl AsRef<Path>) -> Result<Vec<String>> {
+        let source_file = source_file.as_ref();
+        let source_file = source_file
+            .canonicalize()
+            .map_err(|e| eyre!("unable to locate {source_file:?}: {e}"))?;
+
+        let mut matching = self.entries.iter().filter(|entry| {
+            entry
+                .directory
+                .join(&entry.file)
+                .canonicalize()
+                .ok()
+                .as_ref()
+                == Some(&source_file)
+        });
+        let Some(entry) = matching.next() else {
+            bail!(
+                "{source_file:?} is not listed in the compilation database {:?}",
+                self.path
+            );
+        };
+        if matching.next().is_some() {
+            debug!("{source_file:?} was compiled multiple times, using the first command");
+        }
+
+        entry.clang_args()
+    }
+}
+
+impl CompileCommand {
+    /// The args of this command relevant for parsing the source file, see
+    /// [`CompilationDatabase::clang_args_for`]
+    fn clang_args(&self) -> Result<Vec<String>> {
+        /// Flags which take a separate value, and only affect outputs or the linker
+        const DROPPED_WITH_VALUE: &[&str] = &["-o", "-MF", "-MT", "-MQ", "-gen-cdb-fragment-path"];
+
+        /// Flags which only affect outputs
+        const DROPPED: &[&str] = &["--", "-c", "-S", "-E", "-M", "-MM", "-MD", "-MMD", "-MP"];
+
+        let arguments = match (&self.arguments, &self.command) {
+            (Some(arguments), _) => arguments.clone(),
+            (None, Some(command)) => split_shell_words(command)?,
+            (None, None) => bail!("no compile command for {:?}", self.file),
+        };
+        let source_file = self.directory.join(&self.file);
+        let absolute = |path: &str| self.directory.join(path).to_string_lossy().into_owned();
+
+        let mut clang_args = Vec::new();
+        // skip the compiler
+        let mut arguments = arguments.into_iter().skip(1);
+        while let Some(arg) = arguments.next() {
+            if DROPPED_WITH_VALUE.contains(&arg.as_str()) {
+                arguments.next();
+            } else if DROPPED.contains(&arg.as_str())
+                || (arg.starts_with("-o") && arg.len() > 2)
+                || arg.starts_with("-Wl,")
+                || self.directory.join(&arg) == source_file
+            {
+                trace!("dropping {arg:?}");
+            } else if is_path_flag(&arg) {
+                let path = arguments.next().ok_or_else(|| {
+                    eyre!("{arg} without a path in the command of {:?}", self.file)
+                })?;
+                clang_args.push(arg);
+                clang_args.push(absolute(&path));
+            } else if let Some((flag, path)) = split_path_arg(&arg) {
+                clang_args.push(format!("{flag}{}", absolute(path)));
+            } else {
+                clang_args.push(arg);
+            }
+        }
+
+        debug!("clang args of {:?}: {clang_args:?}", self.file);
+        Ok(clang_args)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use super::{CompilationDatabase, expand_response_files, split_path_arg, split_shell_words};
+
+    #[test]
+    fn test_split_shell_words() {
+        assert_eq!(
+            split_shell_words(r#"--target=wasm32 -I "/opt/my headers" -I/usr/my\ include"#)
+                .unwrap(),
+            [
+                "--target=wasm32",
+                "-I",
+                "/opt/my headers",
+                "-I/usr/my include"
+            ]
+        );
+        assert!(split_shell_words("-I \"/opt/unbalanced").is_err());
+    }
+
+    #[test]
+    fn test_response_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = dir.path().join("inner.rsp");
+        let outer = dir.path().join("outer.rsp");
+        fs::write(&inner, "-DINNER='1 + 1'\n").unwrap();
+        fs::write(
+            &outer,
+            format!("--target=wasm32 @{}\n-Wall", inner.display()),
+        )
+        .unwrap();
+
+        let args = [
+            "-DFIRST".to_owned(),
+            format!("@{}", outer.display()),
+            "-DLAST".to_owned(),
+        ];
+        assert_eq!(
+            expand_response_files(&args).unwrap(),
+            [
+                "-DFIRST",
+                "--target=wasm32",
+                "-DINNER=1 + 1",
+                "-Wall",
+                "-DLAST"
+            ]
+        );
+
+        // a response file including itself
+        fs::write(&inner, format!("@{}", inner.display())).unwrap();
+        assert!(expand_response_files(&args).is_err());
+    }
+
+    #[test]
+    fn test_split_path_arg() {
+        assert_eq!(split_path_arg("-Iinclude"), Some(("-I", "include")));
+        assert_eq!(
+            split_path_arg("--sysroot=/opt"),
+            Some(("--sysroot=", "/opt"))
+        );
+        assert_eq!(split_path_arg("-I"), None);
+        assert_eq!(split_path_arg("-include-pch"), None);
+        assert_eq!(split_path_arg("-isystemsys"), None);
+        assert_eq!(split_path_arg("--sysroot"), None);
+    }
+
+    #[test]
+    fn test_compilation_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        fs::create_dir(dir.join("src")).unwrap();
+        fs::write(dir.join("src/main.c"), "").unwrap();
+        fs::write(dir.join("src/other.c"), "").unwrap();
+
+        // as concatenated from clang's fragments, debug build first
+        let database = format!(
+            r#"[
+{{ "directory": "{dir}", "file": "src/main.c", "output": "target/debug/main.wasm", "arguments": ["/usr/bin/clang", "-xc", "src/main.c", "--target=wasm32-unknown-wasi", "-Iinclude", "-isystem", "sys", "--sysroot=/opt/sysroot", "-include-pch", "pch/prefix.pch", "-includeconfig.h", "-DNAME=\"a b\"", "-Wl,--no-entry", "-g", "-gen-cdb-fragment-path", "target/debug/cdb-fragments", "-otarget/debug/main.wasm", "--", "src/main.c"]}},
+{{ "directory": "{dir}", "file": "src/main.c", "arguments": ["/usr/bin/clang", "-Oz", "src/main.c"]}},
+{{ "directory": "{dir}", "file": "{dir}/src/other.c", "command": "clang -c -o other.o -I 'my include' {dir}/src/other.c"}},
+]
+"#,
+            dir = dir.display()
+        );
+        fs::write(dir.join("compile_commands.json"), database).unwrap();
+
+        let database = CompilationDatabase::from_directory(&dir).unwrap();
+        let absolute = |path: &str| dir.join(path).display().to_string();
+        assert_eq!(
+            database.clang_args_for(dir.join("src/main.c")).unwrap(),
+            [
+                "-xc".to_owned(),
+                "--target=wasm32-unknown-wasi".to_owned(),
+                format!("-I{}", absolute("include")),
+                "-isystem".to_owned(),
+                absolute("sys"),
+                "--sysroot=/opt/sysroot".to_owned(),
+                "-include-pch".to_owned(),
+                absolute("pch/prefix.pch"),
+                "-includeconfig.h".to_owned(),
+                "-DNAME=\"a b\"".to_owned(),
+                "-g".to_owned(),
+            ]
+        );
+        assert_eq!(
+            database.clang_args_for(dir.join("src/other.c")).unwrap(),
+            ["-I".to_owned(), absolute("my include")]
+        );
+        assert!(
+            database
+                .clang_args_for(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+                .is_err()
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use c_abi_lens::{EndiannessSwap, OutputFormat};
 use clap::Parser;
 
 #[derive(Parser)]
@@ -7,10 +8,27 @@ use clap::Parser;
 pub struct Cli {
     /// Whether to perform an endianness swap
     ///
-    /// If this flag is not set, endianness is never changed.
-    /// If this flag is set, endianness of all primitive types longer than 1 byte is swapped.
-    #[arg(short, long)]
-    pub endianness_swap: bool,
+    /// With `auto`, endianness of all primitive types longer than 1 byte is swapped exactly if the
+    /// byte order of the host target (see `--host-target`) differs from the one of the foreign
+    /// target clang parses for. `always` and `never` override this decision, e.g.
+    /// `--endianness-swap=never`. Passing the flag without a value is the same as `always`.
+    #[arg(
+        short,
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = EndiannessSwap::Auto,
+        default_missing_value = "always"
+    )]
+    pub endianness_swap: EndiannessSwap,
+
+    /// Target triple of the host the generated C code runs on
+    ///
+    /// Only its architecture is considered, to determine the host's byte order. If not given, the
+    /// host is assumed to be of the same byte order as the one this tool runs on.
+    #[arg(long, value_name = "TRIPLE")]
+    pub host_target: Option<String>,
 
     /// Input C file to consume
     ///
@@ -47,6 +65,163 @@ pub struct Cli {
     #[arg(long)]
     pub only_prototype: bool,
 
+    /// Emit dereference helper for each pointer field
+    ///
+    /// If set, each pointer field additionally comes with a function that follows the guest
+    /// address held by the field, relative to the base address of the guest's linear memory
+    #[arg(long)]
+    pub deref_helpers: bool,
+
+    /// Only consider types with a name matching this regex
+    ///
+    /// May be given multiple times, a type is considered if its name matches any of them. The regex
+    /// has to match the entire name of the struct, union, enum or typedef naming it. If not given,
+    /// all types are considered.
+    #[arg(long, value_name = "REGEX")]
+    pub include_type: Vec<String>,
+
+    /// Skip types with a name matching this regex
+    ///
+    /// May be given multiple times. Takes precedence over `--include-type`.
+    #[arg(long, value_name = "REGEX")]
+    pub exclude_type: Vec<String>,
+
+    /// Skip fields with a name matching this regex
+    ///
+    /// May be given multiple times. The regex has to match the entire `type.field` name, e.g.
+    /// `APEX_MESSAGE\.reserved`. All fields nested within a skipped field are skipped as well.
+    #[arg(long, value_name = "REGEX")]
+    pub exclude_field: Vec<String>,
+
+    /// Only consider types declared in the input file itself
+    ///
+    /// Types pulled in via includes, e.g. libc internals from the sysroot, are skipped unless they
+    /// are declared in a header allowed by `--allow-header`.
+    #[arg(long)]
+    pub main_file_only: bool,
+
+    /// Additionally consider types declared in headers with a path matching this regex
+    ///
+    /// May be given multiple times, implies `--main-file-only`. The regex has to match the entire
+    /// path of the header as found by clang, e.g. `.*/ARINC653\.h`.
+    #[arg(long, value_name = "REGEX")]
+    pub allow_header: Vec<String>,
+
+    /// Export object-like macros with a name matching this regex as constants
+    ///
+    /// May be given multiple times. The regex has to match the entire name of the macro, e.g.
+    /// `SYSTEM_LIMIT_.*`. Each selected macro is evaluated on the foreign target, with the same
+    /// clang args, and emitted as a constant of the same (prefixed) name. Macros which do not
+    /// evaluate to an integer are skipped.
+    #[arg(long = "macro", value_name = "REGEX")]
+    pub macros: Vec<String>,
+
+    /// Companion C header with static assertions on the layout
+    ///
+    /// If set, additionally a header is written to this file which asserts the size of each struct
+    /// and union as well as the offset of each of their fields (except bit-fields) using
+    /// `_Static_assert`. Included in the build for the foreign target, it fails to compile if
+    /// compiler flags or pragmas shift the layout away from the one assumed by the generated code.
+    #[arg(long, value_name = "FILE")]
+    pub static_asserts: Option<PathBuf>,
+
+    /// Standalone C program testing the generated accessors
+    ///
+    /// If set, additionally a C program is written to this file, which contains the header-only C
+    /// library (always with function bodies) followed by a round trip test for each struct and
+    /// union. It writes distinct bit patterns through each setter into a buffer of the struct's
+    /// size, reads them back through the getter and checks that no field clobbers its neighbours.
+    /// It only depends on the C standard library, compile and run it on the host.
+    #[arg(long, value_name = "FILE")]
+    pub self_test: Option<PathBuf>,
+
+    /// Output format
+    ///
+    /// Either a header-only C library, a Rust module which is `no_std` compatible and free of
+    /// unsafe code, a JSON document describing the layout, or a WebAssembly module (as text or
+    /// binary) exporting the macros selected by `--macro` as globals. The Rust accessors always
+    /// convert from/to the foreign byte order, hence `--endianness-swap`, `--only-prototype` and
+    /// `--function-decl-prefix` only apply to C.
+    #[arg(long, value_enum, default_value_t = OutputFormat::C)]
+    pub output_format: OutputFormat,
+
+    /// Makefile-compatible dependency file
+    ///
+    /// If set, additionally a dependency file is written, which states that the output file (as
+    /// well as those of `--static-asserts` and `--self-test`) depends on the input file and on every
+    /// file libclang included while parsing it, e.g. sysroot headers. Include it in a Makefile to
+    /// regenerate the outputs whenever any of them changes.
+    #[arg(long, value_name = "FILE", requires = "output_file")]
+    pub depfile: Option<PathBuf>,
+
+    /// Check that the output files are up to date instead of writing them
+    ///
+    /// The output is generated in memory and compared against the existing contents of the output
+    /// file, as well as those of `--static-asserts` and `--self-test` if given. Any difference is
+    /// printed as a unified diff, and the exit status is 1 if any file is not up to date.
+    #[arg(long, requires = "output_file", conflicts_with_all = ["diff_target", "evolved_from"])]
+    pub check: bool,
+
+    /// Fail if any struct, union, enum or field is skipped
+    ///
+    /// Types and fields for which no accessors can be generated, e.g. due to an unsupported type,
+    /// are logged as errors and skipped by default. With this flag, instead nothing is written and
+    /// the exit status is non-zero.
+    #[arg(long, conflicts_with_all = ["diff_target", "evolved_from"])]
+    pub strict: bool,
+
+    /// Summary of what the output covers, as JSON document
+    ///
+    /// If set, additionally a report is written to this file, listing the types and fields
+    /// accessors were generated for (noting fields only accessible as opaque pointer), as well as
+    /// all types and fields which were skipped and why, each with its source location. With
+    /// `--strict`, the report is written even if the lens is not.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["diff_target", "evolved_from"])]
+    pub report: Option<PathBuf>,
+
+    /// Record paths in the generated output relative to this directory
+    ///
+    /// Applies to the input file and to the paths below this directory passed to include and
+    /// sysroot flags within the clang args, as recorded in the header of the output. This keeps the output the same across checkouts in
+    /// different places, e.g. for `--check`.
+    #[arg(long, value_name = "DIR")]
+    pub relative_to: Option<PathBuf>,
+
+    /// Compare the layout against a second target instead of generating code
+    ///
+    /// The input file is parsed a second time, with `--target=<DIFF_TARGET>` appended to the clang
+    /// args. Differences in size, offset, alignment, signedness and byte order are reported per
+    /// type and field, as human readable text or, with `--output-format json`, as a JSON document.
+    /// The exit status is 1 if any type present on both targets can not be copied verbatim.
+    #[arg(long, value_name = "TRIPLE")]
+    pub diff_target: Option<String>,
+
+    /// Check the ABI evolution from an older revision of the input file instead of generating code
+    ///
+    /// Both revisions are parsed with the same clang args. Per type, each layout change is
+    /// classified as either compatible (e.g. appended or renamed fields) or breaking (e.g. moved
+    /// or resized fields, changed signedness), as human readable text or, with
+    /// `--output-format json`, as a JSON document. The exit status is 1 if any change is breaking.
+    #[arg(long, value_name = "OLD_FILE", conflicts_with = "diff_target")]
+    pub evolved_from: Option<PathBuf>,
+
+    /// Directory containing a `compile_commands.json` to take clang args from
+    ///
+    /// The exact args the source file given by `--compile-source` was compiled with, e.g. its
+    /// target, defines and include paths, are passed to clang after the ones from the environment
+    /// variables and before the ones given after `--`. Thereby the layout is the same as seen by the
+    /// foreign build. A trailing comma after the last entry, as left by concatenating the fragments
+    /// of clang's `-gen-cdb-fragment-path`, is tolerated.
+    #[arg(long, value_name = "DIR")]
+    pub compile_commands: Option<PathBuf>,
+
+    /// Source file to look up in the `compile_commands.json`
+    ///
+    /// Defaults to the input file. Set it if the input file is a header, which is not compiled on
+    /// its own, to one of the source files including it.
+    #[arg(long, value_name = "FILE", requires = "compile_commands")]
+    pub compile_source: Option<PathBuf>,
+
     /// Clang arguments
     ///
     /// These are passed through verbatim to (lib-)clang. Likely you want to set the target
@@ -54,8 +229,9 @@ pub struct Cli {
     ///
     /// -- --target=wasm32-unknown-none
     ///
-    /// These are prefixed with the space separated contents of either the EXTRA_CLANG_ARGS and/or
-    /// the BINDGEN_EXTRA_CLANG_ARGS environment variables. The intent behind this mechanism is, to
+    /// These are prefixed with the shell-word split contents of either the EXTRA_CLANG_ARGS and/or
+    /// the BINDGEN_EXTRA_CLANG_ARGS environment variables, i.e. quoting works as in a POSIX shell.
+    /// Any argument of the form `@<FILE>` is replaced by the args in that response file. The intent behind this mechanism is, to
     /// allow setting up sysroot and other essentials for libclang via environment variables.
     ///
     /// In particular, the choice of BINDGEN_EXTRA_CLANG_ARGS makes it so that a Nix build
@@ -1,11 +1,13 @@
 use clang::TypeKind;
 use color_eyre::{Result, eyre::bail};
+use serde::{Deserialize, Serialize};
 
 /// An enum containing all possible representations of C types known to this library
 ///
 /// For sized types, bails out to at least providing an Opaque void pointer and a size.
 /// For unsized types, does nothing.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum RepresentableCType {
     Integer {
         bytes: u8,
@@ -21,7 +23,10 @@ pub enum RepresentableCType {
     Opaque {
         bytes: Option<u64>,
     },
+    #[serde(rename = "uintptr")]
     UIntPtr,
+    Bool,
+    ConstCharPtr,
     Void,
 }
 
@@ -51,6 +56,15 @@ impl RepresentableCType {
                 })
             }
 
+            // its a pointer into the foreign address space, represented as a foreign width address
+            (Pointer, 2 | 4 | 8, _) => {
+                let size = size_of.try_into().unwrap(); // 2 | 4 | 8 all fit into an u8
+                Ok(RepresentableCType::Integer {
+                    bytes: size,
+                    is_unsigned: true,
+                })
+            }
+
             // its a float of some sorts
             (Float | Double, 4 | 8, _) => {
                 let size = size_of.try_into().unwrap(); // 4 | 8 all fit into an u8
@@ -123,7 +137,9 @@ impl RepresentableCType {
             Self::Opaque { bytes: _ } => {
                 format!("void *{maybe_var_name_with_space_prefix}")
             }
-            Self::UIntPtr => "uintptr_t".into(),
+            Self::UIntPtr => format!("uintptr_t{maybe_var_name_with_space_prefix}"),
+            Self::Bool => format!("_Bool{maybe_var_name_with_space_prefix}"),
+            Self::ConstCharPtr => format!("const char *{maybe_var_name_with_space_prefix}"),
             Self::Void => "void".into(),
         }
     }
@@ -146,7 +162,11 @@ impl RepresentableCType {
             Self::Float { bytes } => (*bytes).into(),
             Self::Array { .. } => self.element_type().element_size_bytes()?,
             Self::Opaque { bytes: Some(bytes) } => *bytes,
-            Self::Opaque { bytes: None } | Self::UIntPtr | Self::Void => {
+            Self::Opaque { bytes: None }
+            | Self::UIntPtr
+            | Self::Bool
+            | Self::ConstCharPtr
+            | Self::Void => {
                 bail!("type {self:?} has no known size")
             }
         })
@@ -180,6 +200,8 @@ impl RepresentableCType {
             | Self::Float { .. }
             | Self::Opaque { .. }
             | Self::UIntPtr
+            | Self::Bool
+            | Self::ConstCharPtr
             | Self::Void => 1,
             Self::Array { length, .. } => *length,
         }
@@ -194,6 +216,8 @@ impl RepresentableCType {
             | Self::Float { .. }
             | Self::Opaque { .. }
             | Self::UIntPtr
+            | Self::Bool
+            | Self::ConstCharPtr
             | Self::Void => 1,
             Self::Array { .. } => {
                 let mut length = 1;
@@ -203,6 +227,19 @@ impl RepresentableCType {
         }
     }
 
+    /// Get the length in elements of each dimension of an array, or no dimension otherwise
+    ///
+    /// The outermost dimension comes first, as in the C declaration of the array
+    pub fn dimensions(&self) -> Vec<u64> {
+        let mut dimensions = Vec::new();
+        self.recurse_into_type(|c_type, _, is_last| {
+            if !is_last {
+                dimensions.push(c_type.length_1d());
+            }
+        });
+        dimensions
+    }
+
     /// Recurse into a nested type, calling a closure for each layer
     ///
     /// Implemented without function recursion.
@@ -338,6 +375,25 @@ mod test {
         assert_eq!(arr.format_as_type(Some("arr")), "uint32_t arr[7][11]")
     }
 
+    #[test]
+    fn test_array_dimensions() {
+        let element = RepresentableCType::Integer {
+            bytes: 8,
+            is_unsigned: false,
+        };
+        let arr = RepresentableCType::Array {
+            element_type: Box::new(RepresentableCType::Array {
+                element_type: Box::new(element.clone()),
+                length: 11,
+            }),
+            length: 7,
+        };
+
+        assert_eq!(arr.dimensions(), [7, 11]);
+        assert_eq!(arr.length(), 77);
+        assert!(element.dimensions().is_empty());
+    }
+
     #[test]
     fn test_format_opaque_types() {
         let types = [
@@ -363,6 +419,23 @@ mod test {
     #[test]
     fn test_format_uintptr_type() {
         assert_eq!(RepresentableCType::UIntPtr.to_string(), "uintptr_t");
+        assert_eq!(
+            RepresentableCType::UIntPtr.format_as_type(Some("i0")),
+            "uintptr_t i0"
+        );
+    }
+
+    #[test]
+    fn test_format_bool_type() {
+        assert_eq!(RepresentableCType::Bool.to_string(), "_Bool");
+    }
+
+    #[test]
+    fn test_format_const_char_ptr_type() {
+        assert_eq!(
+            RepresentableCType::ConstCharPtr.format_as_type(Some("name")),
+            "const char * name"
+        );
     }
 
     #[test]
@@ -1,85 +1,68 @@
 use color_eyre::{
-    Result, Section,
+    Result,
     eyre::{OptionExt, bail, ensure},
 };
-use log::{debug, error, info};
+use log::{error, info};
 
 use crate::code_gen::RepresentableCType;
+use crate::report::{Coverage, SkippedKind};
 
-use super::{CFunc, CSection, CSnippet};
+use super::{
+    AnonymousEnum, CDefine, CFunc, CSection, CSnippet, Endianness, EnumLayout, FieldKind,
+    FieldLayout, MacroConstant, RecordLayout, ScalarMeaning, TargetLayout,
+};
+
+/// Options steering the emitted accessor functions
+#[derive(Debug, Clone)]
+pub struct EmitterConfig {
+    /// Whether the endianness of all primitive types longer than 1 byte is swapped
+    pub swap_endianness: bool,
+
+    /// Whether to emit helpers translating pointer fields into host pointers
+    pub emit_deref_helpers: bool,
+}
 
-/// Emit all functions for a given struct
+/// Emit all functions for a given struct or union
+///
+/// Unions are treated just like structs, except for all their members residing at offset 0.
+/// Fields for which no functions can be emitted are skipped, and recorded in the `coverage`.
 pub fn insert_struct_functions(
     code_snippets: &mut Vec<CSnippet>,
-    struct_: &clang::Entity,
-    swap_endianness: bool,
+    struct_: &RecordLayout,
+    target: &TargetLayout,
+    config: &EmitterConfig,
+    coverage: &mut Coverage,
 ) -> Result<()> {
-    let struct_type = struct_.get_type().ok_or_eyre("struct type is unknown?!")?;
-    let struct_size_bytes = struct_type.get_sizeof()?;
-
-    let struct_name = struct_.get_name().ok_or_eyre("struct has no name")?;
-    info!("generating for struct {struct_name:?}");
-
-    debug!("struct: {struct_name:?} (size: {struct_size_bytes} bytes)");
+    info!("generating for {:?} {:?}", struct_.kind, struct_.name);
 
     // per-struct functions
-    emit_per_struct_functions(code_snippets, &struct_name, struct_type)?;
+    emit_per_struct_functions(code_snippets, struct_);
     code_snippets.push(CSnippet::Newline);
 
     // per-struct-field functions
-    for struct_field in struct_.get_children() {
-        ensure!(
-            struct_field.get_kind() == clang::EntityKind::FieldDecl,
-            "all fields of a struct must be of FieldDecl type"
-        );
-
-        // note down the origin of this error
-        let error_origin = format!("struct {struct_name:?}, field {struct_field:?}");
-
-        let field_name = struct_field
-            .get_name()
-            .ok_or_eyre("unknown name")
-            .section(error_origin.clone())?;
-
-        // get offset of the field and its type, fail gracefully (by just ignoring the field)
-        let (field_offset_bits, field_ty) = match (
-            struct_type.get_offsetof(&field_name),
-            struct_field.get_type(),
-        ) {
-            (Ok(fo), Some(ft)) => (fo, ft),
-            (Ok(_), None) => {
-                error!("skipping {error_origin}: it has no known field type");
-                continue;
-            }
-            (Err(e), Some(_)) => {
-                error!("skipping {error_origin}: getting its offsetof yielded an error:\n{e}");
-                continue;
-            }
-            (Err(e), None) => {
-                error!(
-                    "skipping {error_origin}: it has no known field type and getting its offsetof yielded an error:\n{e}"
-                );
-                continue;
-            }
+    for field in &struct_.fields {
+        let result = match &field.kind {
+            FieldKind::BitField { repr, bit_width } => emit_per_bitfield_functions(
+                code_snippets,
+                &struct_.name,
+                field,
+                repr,
+                *bit_width,
+                target,
+                config,
+            ),
+            _ => emit_per_field_functions(code_snippets, &struct_.name, field, target, config),
         };
 
-        debug!(
-            "    field: {field_name:?} (offset: {} bits)",
-            field_offset_bits
-        );
-
-        if let Err(e) = emit_per_field_functions(
-            code_snippets,
-            &struct_name,
-            &field_name,
-            field_offset_bits,
-            field_ty,
-            swap_endianness,
-        ) {
+        if let Err(e) = result {
             error!(
-                "generating the per-field functions for {error_origin} yielded the following error, skipping it\n{e}"
+                "generating the per-field functions for struct {:?}, field {:?} yielded the following error, skipping it\n{e}",
+                struct_.name,
+                field.name()
             );
-        };
+            let qualified_name = format!("{}.{}", struct_.name, field.name());
+            coverage.skip(SkippedKind::Field, qualified_name, format!("{e:#}"), None);
+        }
     }
     code_snippets.push(CSnippet::Newline);
 
@@ -87,14 +70,11 @@ pub fn insert_struct_functions(
 }
 
 /// Insert the [`CSnippets`] for functions related to a given struct itself
-fn emit_per_struct_functions(
-    code_snippets: &mut Vec<CSnippet>,
-    struct_name: &str,
-    struct_type: clang::Type,
-) -> Result<()> {
+fn emit_per_struct_functions(code_snippets: &mut Vec<CSnippet>, struct_: &RecordLayout) {
+    let struct_name = &struct_.name;
     let function_name_gen = |op| format!("{op}__{struct_name}");
 
-    let struct_size_bytes = struct_type.get_sizeof()?;
+    let struct_size_bytes = struct_.size_bytes;
 
     // section header for this struct
     code_snippets.push(
@@ -122,7 +102,27 @@ fn emit_per_struct_functions(
     }));
     code_snippets.push(CSnippet::Newline);
 
-    Ok(())
+    // helper functions for contiguous arrays of the struct
+    code_snippets.push(CSnippet::from(CFunc {
+        comment: format!(
+            "\
+                `&array_base_addr[i]`\n\
+                \n\
+                Returns the base address of the `i`-th `{struct_name}` in a contiguous array of them, using the foreign size of {struct_size_bytes} bytes per element\
+            "
+        ),
+        return_type: RepresentableCType::Opaque { bytes: None },
+        name: function_name_gen("index"),
+        arguments: vec![
+            (
+                RepresentableCType::Opaque { bytes: None },
+                "array_base_addr".to_owned(),
+            ),
+            (RepresentableCType::UIntPtr, "i".to_owned()),
+        ],
+        body: format!("return (void *)((uint8_t *)array_base_addr + i * {struct_size_bytes});"),
+    }));
+    code_snippets.push(CSnippet::Newline);
 }
 
 /// Insert the [`CSnippets`] for functions related to a given struct's field
@@ -133,24 +133,14 @@ fn emit_per_struct_functions(
 fn emit_per_field_functions(
     code_snippets: &mut Vec<CSnippet>,
     struct_name: &str,
-    field_name: &str,
-    offset_bits: usize,
-    ty: clang::Type,
-    swap_endianness: bool,
+    field: &FieldLayout,
+    target: &TargetLayout,
+    config: &EmitterConfig,
 ) -> Result<()> {
-    if offset_bits % 8 != 0 {
-        bail!("bit offset which is not devisable by 8, this is not implemented yet");
-    }
+    let offset_bytes = field.offset_bytes();
 
-    let offset_bytes = offset_bits / 8;
-
-    let function_name_gen = |op| format!("{op}__{struct_name}__{field_name}");
-
-    // desugar this type so that we know what it actually is
-    let canonical_type = ty.get_canonical_type();
-
-    // find a platform agnostic representation of this type
-    let generic_c_field_repr = RepresentableCType::new(&canonical_type)?;
+    let field_name = field.name();
+    let function_name_gen = |op| format!("{op}__{struct_name}__{}", field.identifier());
 
     code_snippets.push(
         CSection {
@@ -172,7 +162,7 @@ fn emit_per_field_functions(
             return_type: RepresentableCType::UIntPtr,
             name: function_name_gen("sizeof"),
             arguments: vec![],
-            body: format!("return {};", generic_c_field_repr.total_size_bytes()?),
+            body: format!("return {};", field.size_bytes()?),
         }
         .into(),
     );
@@ -192,6 +182,8 @@ fn emit_per_field_functions(
     }.into());
     code_snippets.push(CSnippet::Newline);
 
+    let swap_endianness = config.swap_endianness;
+
     // string to anounce the presence of byte-swapping
     let maybe_endianness_swapped = if swap_endianness {
         ", with endianness swapped"
@@ -199,109 +191,136 @@ fn emit_per_field_functions(
         ""
     };
 
-    // function/macro to perform byte swapping
-    let byte_swap_fn = match generic_c_field_repr.element_size_bytes()? {
-        1 => "".to_owned(),
-        n @ 2 | n @ 4 | n @ 8 => format!("bswap_{}", 8 * n),
-        n => {
-            bail!("unable to perform a byte swap for an integer that is {n} bytes wide")
-        }
-    };
-
-    use clang::TypeKind::*;
-    match (
-        canonical_type.get_kind(),
-        canonical_type.get_element_type(),
-        generic_c_field_repr.element_type(),
-    ) {
+    match &field.kind {
         // integer or float or pointer
-        (
-            CharS | CharU | SChar | UChar | Short | UShort | Int | UInt | Long | ULong | LongLong
-            | ULongLong | Float | Double | Enum,
-            _,
-            _,
-        ) => {
+        FieldKind::Scalar { repr, meaning } => {
+            // function/macro to perform byte swapping
+            let byte_swap_fn = match repr.element_size_bytes()? {
+                1 => "".to_owned(),
+                n @ 2 | n @ 4 | n @ 8 => format!("bswap_{}", 8 * n),
+                n => {
+                    bail!("unable to perform a byte swap for an integer that is {n} bytes wide")
+                }
+            };
+
+            // string to explain the value of function pointers (which on Wasm are table indices)
+            // and enums (which might hold values outside of the declared set)
+            let maybe_value_note = match meaning {
+                ScalarMeaning::FunctionPointer { signature } => format!(
+                    "\n\
+                    \n\
+                    The field is a pointer to a function of signature `{signature}`. On Wasm targets its value\n\
+                    is the index of that function in the function table (exported via `--export-table`)\
+                    "
+                ),
+                ScalarMeaning::Enum { enum_name } => format!(
+                    "\n\
+                    \n\
+                    The field is of type `enum {enum_name}`. Values outside of the declared enumerators are passed\n\
+                    through unaltered, use `is_valid__{enum_name}` to check and `to_string__{enum_name}` to name a value\
+                    "
+                ),
+                ScalarMeaning::Value | ScalarMeaning::DataPointer { .. } => String::default(),
+            };
+
             // C code string that might swap the bytes of `value` or does nothing
-            let maybe_byteswap =
-                if swap_endianness && generic_c_field_repr.element_size_bytes()? != 1 {
-                    format!("value = {byte_swap_fn}(value);\n")
-                } else {
-                    String::default()
-                };
+            let maybe_byteswap = if swap_endianness && repr.element_size_bytes()? != 1 {
+                format!("value = {byte_swap_fn}(value);\n")
+            } else {
+                String::default()
+            };
+
+            // C code strings that load/store `value` from/to the field
+            let field_addr = format!("((uint8_t *) struct_base_addr + {offset_bytes})");
+            let (load_value, store_value) = load_and_store_value(
+                repr,
+                &field_addr,
+                (!maybe_byteswap.is_empty()).then_some(byte_swap_fn.as_str()),
+            );
 
             // getter for integer types
-            code_snippets.insert(code_snippets.len() -2, CFunc {
+            code_snippets.push(CFunc {
                 comment: format!("\
                     Get `{struct_name}.{field_name}`\n\
                     \n\
-                    Returns the field `{field_name}`'s value from an instance of the `{struct_name}` struct{maybe_endianness_swapped}\
+                    Returns the field `{field_name}`'s value from an instance of the `{struct_name}` struct{maybe_endianness_swapped}{maybe_value_note}\
                 "),
-                return_type: generic_c_field_repr.clone(),
+                return_type: repr.clone(),
                 name: function_name_gen("get"),
                 arguments: [
                     (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned())
                 ].into(),
                 body: format!("\
                     {};\n\
-                    memcpy(&value, ((uint8_t *) struct_base_addr + {offset_bytes}), sizeof(value));\n\
-                    {maybe_byteswap}return value;\
-                    ",generic_c_field_repr.format_as_type(Some("value"))
+                    {load_value}return value;\
+                    ",repr.format_as_type(Some("value"))
                 )
             }.into());
-            code_snippets.insert(code_snippets.len() - 2, CSnippet::Newline);
+            code_snippets.push(CSnippet::Newline);
 
             // setter for integer types
-            code_snippets.insert(code_snippets.len() -2, CFunc {
+            code_snippets.push(CFunc {
                 comment: format!("\
                     Set `{struct_name}.{field_name}` to `value`\n\
                     \n\
-                    Overwrites the field `{field_name}`'s value of an `{struct_name}` struct instance with `value`{maybe_endianness_swapped}\
+                    Overwrites the field `{field_name}`'s value of an `{struct_name}` struct instance with `value`{maybe_endianness_swapped}{maybe_value_note}\
                 "),
                 return_type: RepresentableCType::Void,
                 name: function_name_gen("set"),
                 arguments: [
                     (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
-                    (generic_c_field_repr.clone(), "value".to_owned())
+                    (repr.clone(), "value".to_owned())
                 ].into(),
 
-                body: format!("\
-                    {maybe_byteswap}\
-                    memcpy(((uint8_t *) struct_base_addr + {offset_bytes}), &value, sizeof(value));\
-                    "
-                )
+                body: store_value,
             }.into());
-            code_snippets.insert(code_snippets.len() - 2, CSnippet::Newline);
+            code_snippets.push(CSnippet::Newline);
+
+            // dereference helper for pointer types, function pointers do not point into the linear memory
+            if let (ScalarMeaning::DataPointer { pointee_type_name }, true) =
+                (meaning, config.emit_deref_helpers)
+            {
+                let pointer_bits = u16::from(target.pointer_width_bytes) * 8;
+                ensure!(
+                    repr.element_size_bytes()? == u64::from(target.pointer_width_bytes),
+                    "pointer is not {pointer_bits} bits wide, as pointers of the target are"
+                );
+
+                code_snippets.push(CFunc {
+                    comment: format!("\
+                        Dereference `{struct_name}.{field_name}`\n\
+                        \n\
+                        Returns a host pointer to the `{pointee_type_name}` the field `{field_name}` of an `{struct_name}` struct instance points to{maybe_endianness_swapped}\n\
+                        \n\
+                        The field holds a {pointer_bits}-bit guest address, which is interpreted as an offset to `linear_memory_base`.\n\
+                        The returned pointer can be passed as `struct_base_addr` to the accessors of the pointee. No null or bounds checks are performed.\
+                    "),
+                    return_type: RepresentableCType::Opaque { bytes: None },
+                    name: function_name_gen("deref"),
+                    arguments: [
+                        (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
+                        (RepresentableCType::Opaque { bytes: None }, "linear_memory_base".to_owned())
+                    ].into(),
+                    body: format!("\
+                        {};\n\
+                        memcpy(&value, ((uint8_t *) struct_base_addr + {offset_bytes}), sizeof(value));\n\
+                        {maybe_byteswap}return (void *)((uint8_t *)linear_memory_base + value);\
+                        ",repr.format_as_type(Some("value"))
+                    )
+                }.into());
+                code_snippets.push(CSnippet::Newline);
+            }
         }
 
         // an array of primitive types
-        (
-            ConstantArray,
-            Some(_),
-            RepresentableCType::Integer { .. } | RepresentableCType::Float { .. },
-        ) => {
-            let total_bytes = generic_c_field_repr.total_size_bytes()?;
-            let element_bytes = generic_c_field_repr.element_size_bytes()?;
-
-            // C code string that copies from `src_name` to `dst_name` and might swap endianness of elements while doing so
-            let copy_and_maybe_byteswap = |src_name, dst_name| {
-                if swap_endianness && element_bytes != 1 {
-                    // endianness swapping on the target type is not possible for the write case,
-                    // because the target addresses within `struct_base_addr` might not be aligned
-                    format!(
-                        "\
-                        for(uintptr_t i = 0; i < {total_bytes}; i++)\n\
-                        \t((uint8_t *){dst_name})[i] = ((uint8_t *){src_name})[i + {} - (i % {element_bytes})];\
-                        ",
-                        element_bytes - 1
-                    )
-                } else {
-                    // fast path, just memcpy bytewise
-                    format!("memcpy((uint8_t *){dst_name}, (uint8_t *){src_name}, {total_bytes});")
-                }
-            };
+        FieldKind::Array { repr } => {
+            let total_bytes = repr.total_size_bytes()?;
+            let element_bytes = repr.element_size_bytes()?;
+
+            let field_addr = format!("((uint8_t *) struct_base_addr + {offset_bytes})");
 
             // getter for array types
-            code_snippets.insert(code_snippets.len() -2, CFunc {
+            code_snippets.push(CFunc {
                 comment: format!("\
                     Read from `{struct_name}.{field_name}`\n\
                     \n\
@@ -311,14 +330,14 @@ fn emit_per_field_functions(
                 name: function_name_gen("read"),
                 arguments: [
                     (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
-                    (generic_c_field_repr.clone(), "dst".to_owned())
+                    (repr.clone(), "dst".to_owned())
                 ].into(),
-                body: copy_and_maybe_byteswap("struct_base_addr", "dst")
+                body: copy_elements(&field_addr, "dst", total_bytes, element_bytes, swap_endianness)
             }.into());
-            code_snippets.insert(code_snippets.len() - 2, CSnippet::Newline);
+            code_snippets.push(CSnippet::Newline);
 
             // setter for array types
-            code_snippets.insert(code_snippets.len() -2, CFunc {
+            code_snippets.push(CFunc {
                 comment: format!("\
                     Write to `{struct_name}.{field_name}`\n\
                     \n\
@@ -328,35 +347,884 @@ fn emit_per_field_functions(
                 name: function_name_gen("write"),
                 arguments: [
                     (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
-                    (generic_c_field_repr.clone(), "src".to_owned())
+                    (repr.clone(), "src".to_owned())
                 ].into(),
-                body: copy_and_maybe_byteswap("src", "struct_base_addr")
+                body: copy_elements("src", &field_addr, total_bytes, element_bytes, swap_endianness)
             }.into());
-            code_snippets.insert(code_snippets.len() - 2, CSnippet::Newline);
+            code_snippets.push(CSnippet::Newline);
+
+            let element_repr = repr.element_type();
+            let dimensions = repr.dimensions();
+
+            // helper functions for the length of each dimension
+            for func in array_length_functions(
+                struct_name,
+                &field_name,
+                &function_name_gen("length"),
+                &dimensions,
+            ) {
+                code_snippets.push(func.into());
+                code_snippets.push(CSnippet::Newline);
+            }
+
+            let ArrayIndexing {
+                index_arguments,
+                index_subscripts,
+                element_offset_expr,
+            } = ArrayIndexing::new(&dimensions, offset_bytes, element_bytes);
+
+            // C code strings that load/store `value` from/to `element_addr`, swapping endianness
+            // on an integer copy as swapping a float in place would convert it
+            let (load_element, store_element) = if swap_endianness && element_bytes != 1 {
+                let bits = element_bytes * 8;
+                (
+                    format!(
+                        "\
+                        uint{bits}_t bytes;\n\
+                        memcpy(&bytes, element_addr, sizeof(bytes));\n\
+                        bytes = bswap_{bits}(bytes);\n\
+                        memcpy(&value, &bytes, sizeof(value));\n\
+                    "
+                    ),
+                    format!(
+                        "\
+                        uint{bits}_t bytes;\n\
+                        memcpy(&bytes, &value, sizeof(bytes));\n\
+                        bytes = bswap_{bits}(bytes);\n\
+                        memcpy(element_addr, &bytes, sizeof(bytes));\
+                    "
+                    ),
+                )
+            } else {
+                (
+                    "memcpy(&value, element_addr, sizeof(value));\n".to_owned(),
+                    "memcpy(element_addr, &value, sizeof(value));".to_owned(),
+                )
+            };
+
+            // element getter for array types
+            code_snippets.push(CFunc {
+                comment: format!("\
+                    Get `{struct_name}.{field_name}{index_subscripts}`\n\
+                    \n\
+                    Returns one element of the `{field_name}` array field from an instance of the `{struct_name}` struct{maybe_endianness_swapped}\n\
+                    \n\
+                    No bounds checks are performed on the indices\
+                "),
+                return_type: element_repr.clone(),
+                name: function_name_gen("get_at"),
+                arguments: [
+                    (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned())
+                ].into_iter().chain(index_arguments.clone()).collect(),
+                body: format!("\
+                    uint8_t *element_addr = (uint8_t *) struct_base_addr + {element_offset_expr};\n\
+                    {};\n\
+                    {load_element}\
+                    return value;\
+                    ", element_repr.format_as_type(Some("value"))
+                )
+            }.into());
+            code_snippets.push(CSnippet::Newline);
+
+            // element setter for array types
+            code_snippets.push(CFunc {
+                comment: format!("\
+                    Set `{struct_name}.{field_name}{index_subscripts}` to `value`\n\
+                    \n\
+                    Overwrites one element of the `{field_name}` array field of an `{struct_name}` struct instance with `value`{maybe_endianness_swapped}\n\
+                    \n\
+                    No bounds checks are performed on the indices\
+                "),
+                return_type: RepresentableCType::Void,
+                name: function_name_gen("set_at"),
+                arguments: [
+                    (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned())
+                ].into_iter().chain(index_arguments).chain([(element_repr, "value".to_owned())]).collect(),
+                body: format!("\
+                    uint8_t *element_addr = (uint8_t *) struct_base_addr + {element_offset_expr};\n\
+                    {store_element}\
+                    "
+                )
+            }.into());
+            code_snippets.push(CSnippet::Newline);
+        }
+
+        // an array of records, the element's struct accessors are reused on each element
+        FieldKind::RecordArray {
+            element_type_name: element_name,
+            element_size_bytes: element_bytes,
+            dimensions,
+        } => {
+            // accessor via void ptr
+            code_snippets.push(CFunc {
+                comment: format!("\
+                    Get void pointer to `{struct_name}.{field_name}`\n\
+                    \n\
+                    This field is an array of `{element_name}`, its elements are accessible via the `{}` function\
+                ", function_name_gen("at")),
+                return_type: RepresentableCType::Opaque { bytes: None },
+                name: function_name_gen("get"),
+                arguments: [
+                    (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
+                ].into(),
+                body: format!("return (void *)((uint8_t *)struct_base_addr + {offset_bytes});")
+            }.into());
+            code_snippets.push(CSnippet::Newline);
+
+            // helper functions for the length of each dimension
+            for func in array_length_functions(
+                struct_name,
+                &field_name,
+                &function_name_gen("length"),
+                dimensions,
+            ) {
+                code_snippets.push(func.into());
+                code_snippets.push(CSnippet::Newline);
+            }
+
+            let ArrayIndexing {
+                index_arguments,
+                index_subscripts,
+                element_offset_expr,
+            } = ArrayIndexing::new(dimensions, offset_bytes, *element_bytes);
+
+            // element base address accessor
+            code_snippets.push(CFunc {
+                comment: format!("\
+                    Get void pointer to `{struct_name}.{field_name}{index_subscripts}`\n\
+                    \n\
+                    Returns the base address of one `{element_name}` element of the `{field_name}` array field, using the foreign element size of {element_bytes} bytes.\n\
+                    The returned pointer can be passed as `struct_base_addr` to the accessors of `{element_name}`.\n\
+                    \n\
+                    No bounds checks are performed on the indices\
+                "),
+                return_type: RepresentableCType::Opaque { bytes: None },
+                name: function_name_gen("at"),
+                arguments: [
+                    (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned())
+                ].into_iter().chain(index_arguments).collect(),
+                body: format!("return (void *)((uint8_t *)struct_base_addr + {element_offset_expr});")
+            }.into());
+            code_snippets.push(CSnippet::Newline);
+        }
+
+        // a nested record, its members get their own accessors
+        FieldKind::Record { .. } => {
+            // accessor via void ptr
+            code_snippets.push(CFunc {
+                comment: format!("\
+                    Get void pointer to `{struct_name}.{field_name}`\n\
+                    \n\
+                    This field is a record itself, its members are accessible via the `{}__<member>` functions\
+                ", function_name_gen("get")),
+                return_type: RepresentableCType::Opaque { bytes: None },
+                name: function_name_gen("get"),
+                arguments: [
+                    (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
+                ].into(),
+                body: format!("return (void *)((uint8_t *)struct_base_addr + {offset_bytes});")
+            }.into());
+            code_snippets.push(CSnippet::Newline);
         }
 
         // we don't know what to do, so just hand out a void pointer
-        (type_kind, maybe_type, _) => {
+        FieldKind::Opaque {
+            type_kind,
+            type_name,
+            ..
+        } => {
             // accessor via void ptr
-            code_snippets.insert(code_snippets.len() -2, CFunc {
+            code_snippets.push(CFunc {
                 comment: format!("\
                     Get void pointer to `{struct_name}.{field_name}`\n\
                     \n\
                     No ABI compatible representation of this type is known, therefore this just returns a void ptr\n\
                     \n\
-                    type kind:  {type_kind:?}\n\
-                    maybe type: {maybe_type:?}\
+                    type kind: {type_kind}\n\
+                    type:      {type_name}\
                 "),
                 return_type: RepresentableCType::Opaque { bytes: None },
                 name: function_name_gen("get"),
                 arguments: [
-(                    RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
+                    (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
                 ].into(),
                 body: format!("return (void *)((uint8_t *)struct_base_addr + {offset_bytes});")
             }.into());
-            code_snippets.insert(code_snippets.len() - 2, CSnippet::Newline);
+            code_snippets.push(CSnippet::Newline);
         }
+
+        FieldKind::BitField { .. } => unreachable!("bit-fields are emitted separately"),
     }
 
     Ok(())
 }
+
+/// C code strings that load `value` from and store it to the field at `field_addr`
+///
+/// Returns the statements for the getter (without the `return`) and the setter's body. If a
+/// `byte_swap_fn` is given, the bytes of `value` are swapped. Floats are swapped on an integer copy,
+/// as passing them to a byte swap macro would convert their value instead of reinterpreting it.
+fn load_and_store_value(
+    repr: &RepresentableCType,
+    field_addr: &str,
+    byte_swap_fn: Option<&str>,
+) -> (String, String) {
+    match (repr, byte_swap_fn) {
+        (RepresentableCType::Float { bytes }, Some(byte_swap_fn)) => {
+            let bits = u16::from(*bytes) * 8;
+            (
+                format!(
+                    "\
+                    uint{bits}_t bytes;\n\
+                    memcpy(&bytes, {field_addr}, sizeof(bytes));\n\
+                    bytes = {byte_swap_fn}(bytes);\n\
+                    memcpy(&value, &bytes, sizeof(value));\n\
+                    "
+                ),
+                format!(
+                    "\
+                    uint{bits}_t bytes;\n\
+                    memcpy(&bytes, &value, sizeof(bytes));\n\
+                    bytes = {byte_swap_fn}(bytes);\n\
+                    memcpy({field_addr}, &bytes, sizeof(bytes));\
+                    "
+                ),
+            )
+        }
+        (_, Some(byte_swap_fn)) => (
+            format!(
+                "memcpy(&value, {field_addr}, sizeof(value));\nvalue = {byte_swap_fn}(value);\n"
+            ),
+            format!("value = {byte_swap_fn}(value);\nmemcpy({field_addr}, &value, sizeof(value));"),
+        ),
+        (_, None) => (
+            format!("memcpy(&value, {field_addr}, sizeof(value));\n"),
+            format!("memcpy({field_addr}, &value, sizeof(value));"),
+        ),
+    }
+}
+
+/// C code string that copies an array from `src_name` to `dst_name`
+///
+/// If `swap_endianness` is set, the byte order of each element is reversed while copying.
+fn copy_elements(
+    src_name: &str,
+    dst_name: &str,
+    total_bytes: u64,
+    element_bytes: u64,
+    swap_endianness: bool,
+) -> String {
+    if swap_endianness && element_bytes != 1 {
+        // endianness swapping on the target type is not possible for the write case,
+        // because the target addresses within `struct_base_addr` might not be aligned
+        format!(
+            "\
+            for(uintptr_t i = 0; i < {total_bytes}; i++)\n\
+            \t((uint8_t *){dst_name})[i] = ((uint8_t *){src_name})[i + {} - 2 * (i % {element_bytes})];\
+            ",
+            element_bytes - 1
+        )
+    } else {
+        // fast path, just memcpy bytewise
+        format!("memcpy((uint8_t *){dst_name}, (uint8_t *){src_name}, {total_bytes});")
+    }
+}
+
+/// Create the functions returning the length of each dimension of an array field
+fn array_length_functions(
+    struct_name: &str,
+    field_name: &str,
+    length_function_name: &str,
+    dimensions: &[u64],
+) -> Vec<CFunc> {
+    dimensions
+        .iter()
+        .enumerate()
+        .map(|(dimension, length)| CFunc {
+            comment: format!("\
+                Length of dimension {dimension} of `{struct_name}.{field_name}`\n\
+                \n\
+                Returns the number of elements in dimension {dimension} (counting from the outermost) of the `{field_name}` array field\
+            "),
+            return_type: RepresentableCType::UIntPtr,
+            name: format!("{length_function_name}__dim{dimension}"),
+            arguments: vec![],
+            body: format!("return {length};"),
+        })
+        .collect()
+}
+
+/// The pieces required to address one element of a (possibly multi-dimensional) array field
+struct ArrayIndexing {
+    /// One index argument per dimension
+    index_arguments: Vec<(RepresentableCType, String)>,
+
+    /// The index arguments as C subscripts, e.g. `[i0][i1]`
+    index_subscripts: String,
+
+    /// C expression for the byte offset of the indexed element from the struct base address
+    element_offset_expr: String,
+}
+
+impl ArrayIndexing {
+    fn new(dimensions: &[u64], offset_bytes: u64, element_bytes: u64) -> Self {
+        let index_arguments: Vec<_> = (0..dimensions.len())
+            .map(|dimension| (RepresentableCType::UIntPtr, format!("i{dimension}")))
+            .collect();
+        let index_subscripts = index_arguments
+            .iter()
+            .map(|(_, name)| format!("[{name}]"))
+            .collect();
+
+        // row-major order, as in C
+        let element_index_expr = dimensions
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold("i0".to_owned(), |expr, (dimension, length)| {
+                format!("({expr}) * {length} + i{dimension}")
+            });
+        let element_offset_expr =
+            format!("{offset_bytes} + ({element_index_expr}) * {element_bytes}");
+
+        Self {
+            index_arguments,
+            index_subscripts,
+            element_offset_expr,
+        }
+    }
+}
+
+/// Insert the [`CSnippets`] for functions related to a given struct's bit-field
+///
+/// # Notes
+///
+/// - the bytes spanned by the bit-field are copied into a zero-initialized window integer via
+///   `memcpy`, so that neither unaligned pointers are created nor bytes beyond the bit-field are
+///   touched
+/// - the window is placed within its integer such that it reads as the foreign target's integer
+///   representation after an optional byte swap, then the foreign bit layout is applied
+fn emit_per_bitfield_functions(
+    code_snippets: &mut Vec<CSnippet>,
+    struct_name: &str,
+    field: &FieldLayout,
+    generic_c_field_repr: &RepresentableCType,
+    bit_width: u64,
+    target: &TargetLayout,
+    config: &EmitterConfig,
+) -> Result<()> {
+    let field_name = field.name();
+    let function_name_gen = |op| format!("{op}__{struct_name}__{}", field.identifier());
+
+    let RepresentableCType::Integer { is_unsigned, .. } = *generic_c_field_repr else {
+        bail!("bit-fields of type {generic_c_field_repr:?} are not supported");
+    };
+
+    // the bytes spanned by this bit-field
+    let offset_bits = field.offset_bits;
+    let offset_bytes = field.offset_bytes();
+    let bit_offset_in_window = offset_bits % 8;
+    let window_bytes = field.size_bytes()?;
+
+    // the smallest integer that can hold all spanned bytes
+    let window_int_bytes = window_bytes.next_power_of_two();
+    let window_int_repr = RepresentableCType::Integer {
+        bytes: window_int_bytes
+            .try_into()
+            .ok()
+            .ok_or_eyre("bit-field spans too many bytes")?,
+        is_unsigned: true,
+    };
+
+    // where the spanned bytes go within the window integer and where the bit-field is within them
+    let (window_byte_pos, shift) = match target.endianness {
+        Endianness::Little => (0, bit_offset_in_window),
+        Endianness::Big => (
+            window_int_bytes - window_bytes,
+            window_bytes * 8 - bit_offset_in_window - bit_width,
+        ),
+    };
+    let mask: u64 = u64::MAX >> (64 - bit_width);
+    let sign_bit: u64 = 1 << (bit_width - 1);
+
+    // string to anounce the presence of byte-swapping
+    let maybe_endianness_swapped = if config.swap_endianness {
+        ", with endianness swapped"
+    } else {
+        ""
+    };
+
+    // C code string that might swap the bytes of `window` or does nothing
+    let maybe_byteswap = if config.swap_endianness && window_int_bytes != 1 {
+        format!("window = bswap_{}(window);\n", 8 * window_int_bytes)
+    } else {
+        String::default()
+    };
+
+    // C code string that loads the spanned bytes into `window`
+    let load_window = format!(
+        "\
+        {} = 0;\n\
+        memcpy((uint8_t *)&window + {window_byte_pos}, ((uint8_t *) struct_base_addr + {offset_bytes}), {window_bytes});\n\
+        {maybe_byteswap}\
+        ",
+        window_int_repr.format_as_type(Some("window"))
+    );
+
+    // C code string that sign-extends `value`, or does nothing
+    let maybe_sign_extend = if is_unsigned {
+        String::default()
+    } else {
+        format!("value = (value ^ {sign_bit:#x}ull) - {sign_bit:#x}ull;\n")
+    };
+
+    code_snippets.push(
+        CSection {
+            title: format!(" {struct_name}.{field_name} "),
+            comment: Default::default(),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+
+    // helper functions for size of the bytes spanned by the bit-field
+    code_snippets.push(
+        CFunc {
+            comment: format!("\
+                Bytes spanned by `{struct_name}.{field_name}`\n\
+                \n\
+                Returns the number of bytes touched by the `{field_name}` bit-field from the `{struct_name}` struct\
+            "),
+            return_type: RepresentableCType::UIntPtr,
+            name: function_name_gen("sizeof"),
+            arguments: vec![],
+            body: format!("return {window_bytes};"),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+
+    // helper functions for offset of the first byte spanned by the bit-field
+    code_snippets.push(CFunc {
+        comment: format!("\
+            Byte offset of `{struct_name}.{field_name}`\n\
+            \n\
+            Get the offset in bytes of the first byte touched by the `{field_name}` bit-field from the start of a `{struct_name}` struct\
+        "),
+        return_type: RepresentableCType::UIntPtr,
+        name: function_name_gen("offsetof"),
+        arguments: vec![],
+        body: format!("return {offset_bytes};"),
+    }.into());
+    code_snippets.push(CSnippet::Newline);
+
+    // helper functions for bit offset of the bit-field
+    code_snippets.push(CFunc {
+        comment: format!("\
+            Bit offset of `{struct_name}.{field_name}`\n\
+            \n\
+            Get the offset in bits of the `{field_name}` bit-field from the start of a `{struct_name}` struct, as laid out by the foreign target\
+        "),
+        return_type: RepresentableCType::UIntPtr,
+        name: function_name_gen("bitoffsetof"),
+        arguments: vec![],
+        body: format!("return {offset_bits};"),
+    }.into());
+    code_snippets.push(CSnippet::Newline);
+
+    // helper functions for width of the bit-field
+    code_snippets.push(
+        CFunc {
+            comment: format!(
+                "\
+            Bit width of `{struct_name}.{field_name}`\n\
+            \n\
+            Get the width in bits of the `{field_name}` bit-field from the `{struct_name}` struct\
+        "
+            ),
+            return_type: RepresentableCType::UIntPtr,
+            name: function_name_gen("bitwidthof"),
+            arguments: vec![],
+            body: format!("return {bit_width};"),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+
+    // getter for bit-fields
+    code_snippets.push(CFunc {
+        comment: format!("\
+            Get `{struct_name}.{field_name}`\n\
+            \n\
+            Returns the bit-field `{field_name}`'s value from an instance of the `{struct_name}` struct{maybe_endianness_swapped}\
+        "),
+        return_type: generic_c_field_repr.clone(),
+        name: function_name_gen("get"),
+        arguments: [
+            (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned())
+        ].into(),
+        body: format!("\
+            {load_window}\
+            uint64_t value = ((uint64_t)window >> {shift}) & {mask:#x}ull;\n\
+            {maybe_sign_extend}\
+            return ({generic_c_field_repr})value;\
+            "
+        )
+    }.into());
+    code_snippets.push(CSnippet::Newline);
+
+    // setter for bit-fields
+    code_snippets.push(CFunc {
+        comment: format!("\
+            Set `{struct_name}.{field_name}` to `value`\n\
+            \n\
+            Overwrites the bit-field `{field_name}`'s value of an `{struct_name}` struct instance with `value`{maybe_endianness_swapped}\n\
+            \n\
+            Only the lower {bit_width} bits of `value` are stored, all other bits spanned by the bit-field's bytes are preserved\
+        "),
+        return_type: RepresentableCType::Void,
+        name: function_name_gen("set"),
+        arguments: [
+            (RepresentableCType::Opaque { bytes: None }, "struct_base_addr".to_owned()),
+            (generic_c_field_repr.clone(), "value".to_owned())
+        ].into(),
+        body: format!("\
+            {load_window}\
+            window = ({window_int_repr})(((uint64_t)window & ~{:#x}ull) | (((uint64_t)value & {mask:#x}ull) << {shift}));\n\
+            {maybe_byteswap}\
+            memcpy(((uint8_t *) struct_base_addr + {offset_bytes}), (uint8_t *)&window + {window_byte_pos}, {window_bytes});\
+            ",
+            mask << shift
+        )
+    }.into());
+    code_snippets.push(CSnippet::Newline);
+
+    Ok(())
+}
+
+/// Emit all constants and functions for a given enum
+///
+/// Each enumerator becomes a constant holding its value on the foreign target, accompanied by
+/// functions to check whether a value is a declared enumerator and to get an enumerator's name.
+pub fn insert_enum_functions(code_snippets: &mut Vec<CSnippet>, enum_: &EnumLayout) -> Result<()> {
+    let enum_name = &enum_.name;
+    let generic_c_enum_repr = &enum_.repr;
+    let RepresentableCType::Integer { is_unsigned, .. } = *generic_c_enum_repr else {
+        bail!("enums with an underlying type of {generic_c_enum_repr:?} are not supported");
+    };
+
+    info!("generating for enum {enum_name:?}");
+
+    let function_name_gen = |op| format!("{op}__{enum_name}");
+
+    // section header for this enum
+    code_snippets.push(
+        CSection {
+            title: format!(" enum {enum_name} "),
+            comment: Default::default(),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+
+    // the name and C literal for each distinct value, the first enumerator of a value wins
+    let mut distinct_values: Vec<(u64, &str, String)> = Vec::new();
+
+    for enumerator in &enum_.enumerators {
+        let enumerator_name = &enumerator.name;
+        let value = integer_constant(
+            generic_c_enum_repr,
+            is_unsigned,
+            enumerator.signed_value,
+            enumerator.unsigned_value,
+        );
+
+        code_snippets.push(
+            CDefine {
+                comment: format!(
+                    "\
+                    `{enumerator_name}`\n\
+                    \n\
+                    Value of the `{enumerator_name}` enumerator of `enum {enum_name}` on the foreign architecture\
+                    "
+                ),
+                name: format!("enum__{enum_name}__{enumerator_name}"),
+                value: value.clone(),
+            }
+            .into(),
+        );
+
+        if !distinct_values
+            .iter()
+            .any(|(v, ..)| *v == enumerator.unsigned_value)
+        {
+            distinct_values.push((enumerator.unsigned_value, enumerator_name, value));
+        }
+    }
+    code_snippets.push(CSnippet::Newline);
+
+    // switch over all distinct values, `case_body` yields the statement for each value
+    let switch_gen = |case_body: &dyn Fn(&str) -> String, default_body: &str| {
+        let mut body = String::from("switch (value) {\n");
+        for (_, enumerator_name, value) in &distinct_values {
+            body.push_str(&format!(
+                "case {value}:\n\t{}\n",
+                case_body(enumerator_name)
+            ));
+        }
+        body.push_str(&format!("default:\n\t{default_body}\n}}"));
+        body
+    };
+
+    // helper function to check for declared values
+    code_snippets.push(
+        CFunc {
+            comment: format!(
+                "\
+                Check whether `value` is an `enum {enum_name}`\n\
+                \n\
+                Returns true if `value` equals any of the enumerators declared for `enum {enum_name}`\
+                "
+            ),
+            return_type: RepresentableCType::Bool,
+            name: function_name_gen("is_valid"),
+            arguments: [(generic_c_enum_repr.clone(), "value".to_owned())].into(),
+            body: switch_gen(&|_| "return 1;".to_owned(), "return 0;"),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+
+    // helper function to name a value
+    code_snippets.push(
+        CFunc {
+            comment: format!(
+                "\
+                Name `value` as `enum {enum_name}`\n\
+                \n\
+                Returns the name of the (first) enumerator of `enum {enum_name}` equal to `value`, or NULL if there is none\
+                "
+            ),
+            return_type: RepresentableCType::ConstCharPtr,
+            name: function_name_gen("to_string"),
+            arguments: [(generic_c_enum_repr.clone(), "value".to_owned())].into(),
+            body: switch_gen(&|name| format!("return \"{name}\";"), "return NULL;"),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+    code_snippets.push(CSnippet::Newline);
+
+    Ok(())
+}
+
+/// C expression for an integer constant of a given type
+fn integer_constant(
+    repr: &RepresentableCType,
+    is_unsigned: bool,
+    signed_value: i64,
+    unsigned_value: u64,
+) -> String {
+    let literal = match (is_unsigned, signed_value) {
+        (true, _) => format!("{unsigned_value}ull"),
+        // the negation of `9223372036854775808ll` would overflow
+        (false, i64::MIN) => format!("{}ll - 1", i64::MIN + 1),
+        (false, signed_value) => format!("{signed_value}ll"),
+    };
+    format!("(({repr})({literal}))")
+}
+
+/// Emit a constant for each evaluated macro
+///
+/// The constants are named just like the macros, so that with a prefix applied they can coexist
+/// with the foreign target's definitions.
+pub fn insert_macro_constants(code_snippets: &mut Vec<CSnippet>, constants: &[MacroConstant]) {
+    if constants.is_empty() {
+        return;
+    }
+
+    info!("generating for {} macros", constants.len());
+
+    // section header for all constants
+    code_snippets.push(
+        CSection {
+            title: " constants ".to_owned(),
+            comment: Default::default(),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+
+    for MacroConstant {
+        name,
+        repr,
+        signed_value,
+        unsigned_value,
+    } in constants
+    {
+        let is_unsigned = matches!(
+            repr,
+            RepresentableCType::Integer {
+                is_unsigned: true,
+                ..
+            }
+        );
+
+        code_snippets.push(
+            CDefine {
+                comment: format!(
+                    "\
+                    `{name}`\n\
+                    \n\
+                    Value of the `{name}` macro on the foreign architecture\
+                    "
+                ),
+                name: name.clone(),
+                value: integer_constant(repr, is_unsigned, *signed_value, *unsigned_value),
+            }
+            .into(),
+        );
+    }
+    code_snippets.push(CSnippet::Newline);
+    code_snippets.push(CSnippet::Newline);
+}
+
+/// Emit a constant for each enumerator of the enums without any name
+///
+/// The constants are named `enum__{enumerator}`, analogous to those of named enums.
+pub fn insert_anonymous_enum_constants(
+    code_snippets: &mut Vec<CSnippet>,
+    anonymous_enums: &[AnonymousEnum],
+) -> Result<()> {
+    if anonymous_enums.is_empty() {
+        return Ok(());
+    }
+
+    info!("generating for {} anonymous enums", anonymous_enums.len());
+
+    // section header for all anonymous enums
+    code_snippets.push(
+        CSection {
+            title: " anonymous enums ".to_owned(),
+            comment: Default::default(),
+        }
+        .into(),
+    );
+    code_snippets.push(CSnippet::Newline);
+
+    for AnonymousEnum { repr, enumerators } in anonymous_enums {
+        let RepresentableCType::Integer { is_unsigned, .. } = *repr else {
+            bail!("enums with an underlying type of {repr:?} are not supported");
+        };
+
+        for enumerator in enumerators {
+            let enumerator_name = &enumerator.name;
+            code_snippets.push(
+                CDefine {
+                    comment: format!(
+                        "\
+                        `{enumerator_name}`\n\
+                        \n\
+                        Value of the `{enumerator_name}` enumerator of an anonymous enum on the foreign architecture\
+                        "
+                    ),
+                    name: format!("enum__{enumerator_name}"),
+                    value: integer_constant(
+                        repr,
+                        is_unsigned,
+                        enumerator.signed_value,
+                        enumerator.unsigned_value,
+                    ),
+                }
+                .into(),
+            );
+        }
+    }
+    code_snippets.push(CSnippet::Newline);
+    code_snippets.push(CSnippet::Newline);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{copy_elements, load_and_store_value};
+    use crate::code_gen::RepresentableCType;
+
+    const FIELD_ADDR: &str = "((uint8_t *) struct_base_addr + 12)";
+
+    #[test]
+    fn test_copy_elements_at_offset() {
+        let read = copy_elements(FIELD_ADDR, "dst", 6, 1, true);
+        let write = copy_elements("src", FIELD_ADDR, 6, 1, true);
+
+        assert_eq!(
+            read,
+            "memcpy((uint8_t *)dst, (uint8_t *)((uint8_t *) struct_base_addr + 12), 6);"
+        );
+        assert_eq!(
+            write,
+            "memcpy((uint8_t *)((uint8_t *) struct_base_addr + 12), (uint8_t *)src, 6);"
+        );
+    }
+
+    #[test]
+    fn test_copy_elements_byteswapped() {
+        let read = copy_elements(FIELD_ADDR, "dst", 12, 4, true);
+
+        assert_eq!(
+            read,
+            "for(uintptr_t i = 0; i < 12; i++)\n\
+            \t((uint8_t *)dst)[i] = ((uint8_t *)((uint8_t *) struct_base_addr + 12))[i + 3 - 2 * (i % 4)];"
+        );
+
+        // the emitted index has to reverse every element on its own
+        let swapped_index = |i: u64| i + 3 - 2 * (i % 4);
+        let indices: Vec<_> = (0..12).map(swapped_index).collect();
+        assert_eq!(indices, [3, 2, 1, 0, 7, 6, 5, 4, 11, 10, 9, 8]);
+    }
+
+    #[test]
+    fn test_load_and_store_swapped_float() {
+        let (load, store) = load_and_store_value(
+            &RepresentableCType::Float { bytes: 4 },
+            FIELD_ADDR,
+            Some("bswap_32"),
+        );
+
+        assert_eq!(
+            load,
+            "uint32_t bytes;\n\
+            memcpy(&bytes, ((uint8_t *) struct_base_addr + 12), sizeof(bytes));\n\
+            bytes = bswap_32(bytes);\n\
+            memcpy(&value, &bytes, sizeof(value));\n"
+        );
+        assert_eq!(
+            store,
+            "uint32_t bytes;\n\
+            memcpy(&bytes, &value, sizeof(bytes));\n\
+            bytes = bswap_32(bytes);\n\
+            memcpy(((uint8_t *) struct_base_addr + 12), &bytes, sizeof(bytes));"
+        );
+    }
+
+    #[test]
+    fn test_load_and_store_swapped_integer() {
+        let repr = RepresentableCType::Integer {
+            bytes: 2,
+            is_unsigned: false,
+        };
+        let (load, store) = load_and_store_value(&repr, FIELD_ADDR, Some("bswap_16"));
+
+        assert_eq!(
+            load,
+            "memcpy(&value, ((uint8_t *) struct_base_addr + 12), sizeof(value));\n\
+            value = bswap_16(value);\n"
+        );
+        assert_eq!(
+            store,
+            "value = bswap_16(value);\n\
+            memcpy(((uint8_t *) struct_base_addr + 12), &value, sizeof(value));"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::PathBuf;
+
+use color_eyre::{Result, eyre::ensure};
+use serde::{Deserialize, Serialize};
+
+use super::LensLayout;
+
+/// Version of the JSON layout schema
+///
+/// Bumped on every change that could break a consumer, i.e. whenever a property is removed,
+/// renamed or changes its meaning. Adding properties does not bump the version.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// The JSON layout document, see the README for a description of the schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutDocument {
+    /// Always [`JSON_SCHEMA_VERSION`] when written by this tool
+    pub schema_version: u32,
+
+    /// Name and version of the tool which wrote this document
+    pub generated_by: String,
+
+    /// The file the layout was gathered from
+    pub input_file: PathBuf,
+
+    /// The arguments passed to clang while parsing the `input_file`
+    pub clang_args: Vec<String>,
+
+    #[serde(flatten)]
+    pub layout: LensLayout,
+}
+
+impl LayoutDocument {
+    /// Serialize this document as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        Ok(json)
+    }
+
+    /// Deserialize a document, refusing any other schema version than the one known to this tool
+    #[allow(dead_code)]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let document: Self = serde_json::from_str(json)?;
+        ensure!(
+            document.schema_version == JSON_SCHEMA_VERSION,
+            "unsupported layout schema version {}, expected {JSON_SCHEMA_VERSION}",
+            document.schema_version
+        );
+        Ok(document)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{JSON_SCHEMA_VERSION, LayoutDocument};
+    use crate::code_gen::{
+        Endianness, EnumLayout, Enumerator, FieldKind, FieldLayout, LensLayout, RecordKind,
+        RecordLayout, RepresentableCType, ScalarMeaning, TargetLayout, TypeLayout,
+    };
+
+    fn example_document() -> LayoutDocument {
+        let int32 = RepresentableCType::Integer {
+            bytes: 4,
+            is_unsigned: false,
+        };
+
+        LayoutDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            generated_by: "c-abi-lens v0.0.0".to_owned(),
+            input_file: "example.h".into(),
+            clang_args: vec!["--target=wasm32-unknown-none".to_owned()],
+            layout: LensLayout {
+                target: TargetLayout {
+                    triple: "wasm32-unknown-none".to_owned(),
+                    endianness: Endianness::Little,
+                    pointer_width_bytes: 4,
+                },
+                types: vec![
+                    TypeLayout::Record(RecordLayout {
+                        name: "Example".to_owned(),
+                        c_type: "Example".to_owned(),
+                        kind: RecordKind::Struct,
+                        size_bytes: 12,
+                        align_bytes: 4,
+                        fields: vec![
+                            FieldLayout {
+                                path: vec!["ptr".to_owned()],
+                                offset_bits: 0,
+                                align_bytes: 4,
+                                kind: FieldKind::Scalar {
+                                    repr: RepresentableCType::Integer {
+                                        bytes: 4,
+                                        is_unsigned: true,
+                                    },
+                                    meaning: ScalarMeaning::DataPointer {
+                                        pointee_type_name: "char".to_owned(),
+                                    },
+                                },
+                            },
+                            FieldLayout {
+                                path: vec!["flags".to_owned()],
+                                offset_bits: 35,
+                                align_bytes: 4,
+                                kind: FieldKind::BitField {
+                                    repr: int32.clone(),
+                                    bit_width: 3,
+                                },
+                            },
+                            FieldLayout {
+                                path: vec!["matrix".to_owned()],
+                                offset_bits: 64,
+                                align_bytes: 1,
+                                kind: FieldKind::Array {
+                                    repr: RepresentableCType::Array {
+                                        element_type: Box::new(RepresentableCType::Array {
+                                            element_type: Box::new(RepresentableCType::Integer {
+                                                bytes: 1,
+                                                is_unsigned: true,
+                                            }),
+                                            length: 2,
+                                        }),
+                                        length: 2,
+                                    },
+                                },
+                            },
+                        ],
+                    }),
+                    TypeLayout::Enum(EnumLayout {
+                        name: "Color".to_owned(),
+                        repr: int32,
+                        enumerators: vec![Enumerator {
+                            name: "RED".to_owned(),
+                            signed_value: -1,
+                            unsigned_value: u64::MAX,
+                        }],
+                    }),
+                ],
+                constants: vec![],
+                anonymous_enums: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let document = example_document();
+        let json = document.to_json().unwrap();
+        assert_eq!(LayoutDocument::from_json(&json).unwrap(), document);
+    }
+
+    #[test]
+    fn test_json_schema_shape() {
+        let json: serde_json::Value =
+            serde_json::from_str(&example_document().to_json().unwrap()).unwrap();
+
+        assert_eq!(json["schema_version"], JSON_SCHEMA_VERSION);
+        assert_eq!(json["target"]["endianness"], "little");
+
+        let record = &json["types"][0];
+        assert_eq!(record["kind"], "record");
+        assert_eq!(record["record_kind"], "struct");
+
+        let pointer = &record["fields"][0];
+        assert_eq!(pointer["kind"], "scalar");
+        assert_eq!(pointer["repr"]["kind"], "integer");
+        assert_eq!(pointer["meaning"]["kind"], "data_pointer");
+
+        let bit_field = &record["fields"][1];
+        assert_eq!(bit_field["kind"], "bit_field");
+        assert_eq!(bit_field["bit_width"], 3);
+
+        assert_eq!(json["types"][1]["kind"], "enum");
+    }
+
+    #[test]
+    fn test_json_rejects_other_schema_version() {
+        let mut document = example_document();
+        document.schema_version = JSON_SCHEMA_VERSION + 1;
+        let json = document.to_json().unwrap();
+        assert!(LayoutDocument::from_json(&json).is_err());
+    }
+}
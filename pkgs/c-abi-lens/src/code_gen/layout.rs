@@ -0,0 +1,758 @@
+use std::collections::HashMap;
+
+use clang::{EntityKind, TypeKind};
+use color_eyre::{
+    Result, Section,
+    eyre::{OptionExt, bail, ensure},
+};
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+
+use super::{Endianness, RepresentableCType, TypeFilter};
+use crate::report::{Coverage, SkippedKind, SourceLocation};
+
+/// Everything known about the foreign ABI's data types in one translation unit
+///
+/// This is the language agnostic intermediate representation from which all outputs are
+/// generated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LensLayout {
+    pub target: TargetLayout,
+    pub types: Vec<TypeLayout>,
+
+    /// Selected object-like macros with an integer value
+    #[serde(default)]
+    pub constants: Vec<MacroConstant>,
+
+    /// Enums which neither have a name nor are named by a typedef, only their enumerators are of
+    /// use
+    #[serde(default)]
+    pub anonymous_enums: Vec<AnonymousEnum>,
+}
+
+/// Properties of the foreign target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetLayout {
+    /// The normalized LLVM target triple
+    pub triple: String,
+
+    /// Byte order of the foreign target, determines the bit layout of bit-fields
+    pub endianness: Endianness,
+
+    /// Width of a pointer on the foreign target in bytes
+    pub pointer_width_bytes: u8,
+}
+
+/// A named type declaration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypeLayout {
+    Record(RecordLayout),
+    Enum(EnumLayout),
+}
+
+/// Layout of a struct or union on the foreign target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordLayout {
+    /// Name of the record, or the typedef naming it
+    pub name: String,
+
+    /// Spelling of the type in C, e.g. `struct name` or just `name` for a typedef
+    pub c_type: String,
+    #[serde(rename = "record_kind")]
+    pub kind: RecordKind,
+    pub size_bytes: u64,
+    pub align_bytes: u64,
+
+    /// All fields, including those of nested records (directly after the nested record itself)
+    pub fields: Vec<FieldLayout>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    Struct,
+    Union,
+}
+
+/// Layout of one (possibly nested) field of a record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldLayout {
+    /// Names of the fields leading from the outermost record to this field
+    pub path: Vec<String>,
+
+    /// Offset from the start of the outermost record
+    pub offset_bits: u64,
+
+    /// Alignment of the field's type (not of the field, which might be packed)
+    pub align_bytes: u64,
+    #[serde(flatten)]
+    pub kind: FieldKind,
+}
+
+/// How a field is represented, determines which accessors are generated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FieldKind {
+    /// Integer, float, enum or pointer
+    Scalar {
+        repr: RepresentableCType,
+        meaning: ScalarMeaning,
+    },
+
+    /// Integer or enum bit-field, spanning at most 8 bytes
+    BitField {
+        repr: RepresentableCType,
+        bit_width: u64,
+    },
+
+    /// (Multi-dimensional) array of integers or floats
+    Array { repr: RepresentableCType },
+
+    /// (Multi-dimensional) array of structs or unions
+    RecordArray {
+        element_type_name: String,
+        element_size_bytes: u64,
+        dimensions: Vec<u64>,
+    },
+
+    /// Nested struct or union, its members are listed as fields of their own
+    Record { type_name: String, size_bytes: u64 },
+
+    /// No ABI compatible representation of this type is known
+    Opaque {
+        type_kind: String,
+        type_name: String,
+        size_bytes: u64,
+    },
+}
+
+/// What the value of a [`FieldKind::Scalar`] means
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScalarMeaning {
+    Value,
+    Enum { enum_name: String },
+    DataPointer { pointee_type_name: String },
+    FunctionPointer { signature: String },
+}
+
+/// The enumerators of an enum on the foreign target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnumLayout {
+    /// Name of the enum, or the typedef naming it
+    pub name: String,
+    pub repr: RepresentableCType,
+    pub enumerators: Vec<Enumerator>,
+}
+
+/// The enumerators of an enum without any name on the foreign target, e.g. `enum { A, B };`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnonymousEnum {
+    pub repr: RepresentableCType,
+    pub enumerators: Vec<Enumerator>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Enumerator {
+    pub name: String,
+    pub signed_value: i64,
+    pub unsigned_value: u64,
+}
+
+/// Value of an object-like macro on the foreign target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroConstant {
+    pub name: String,
+
+    /// Representation of the type the macro's value has on the foreign target
+    pub repr: RepresentableCType,
+    pub signed_value: i64,
+    pub unsigned_value: u64,
+}
+
+impl FieldLayout {
+    /// The path joined with `.`, as used in C member access
+    pub fn name(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// The path joined with `__`, as used in identifiers
+    pub fn identifier(&self) -> String {
+        self.path.join("__")
+    }
+
+    /// Offset of the first byte (partially) occupied by this field
+    pub fn offset_bytes(&self) -> u64 {
+        self.offset_bits / 8
+    }
+
+    /// Number of bytes (partially) occupied by this field
+    pub fn size_bytes(&self) -> Result<u64> {
+        Ok(match &self.kind {
+            FieldKind::Scalar { repr, .. } | FieldKind::Array { repr } => {
+                repr.total_size_bytes()?
+            }
+            FieldKind::BitField { bit_width, .. } => (self.offset_bits % 8 + bit_width).div_ceil(8),
+            FieldKind::RecordArray {
+                element_size_bytes,
+                dimensions,
+                ..
+            } => element_size_bytes * dimensions.iter().product::<u64>(),
+            FieldKind::Record { size_bytes, .. } | FieldKind::Opaque { size_bytes, .. } => {
+                *size_bytes
+            }
+        })
+    }
+}
+
+impl LensLayout {
+    /// Gather the layout of all structs, unions and enums declared at the top level of `tu`
+    ///
+    /// Only types and fields selected by the `filter` are gathered. Types which can not be handled
+    /// are skipped, as are fields within records which can not be handled. Both are logged as
+    /// errors and recorded in the `coverage`, as is the location of each gathered type and field.
+    /// Of enums without any name, the enumerators with a name selected by the `filter` are
+    /// gathered.
+    pub fn from_translation_unit(
+        tu: &clang::TranslationUnit,
+        filter: &TypeFilter,
+        coverage: &mut Coverage,
+    ) -> Result<Self> {
+        let clang::Target {
+            triple,
+            pointer_width,
+        } = tu.get_target();
+        let target = TargetLayout {
+            endianness: Endianness::of_target_triple(&triple),
+            triple,
+            pointer_width_bytes: (pointer_width / 8).try_into()?,
+        };
+        debug!("target: {target:?}");
+
+        let tu_children = tu.get_entity().get_children();
+
+        // Get the structs, unions and enums in this translation unit, forward declarations have
+        // no layout
+        let type_decls = tu_children
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e.get_kind(),
+                    EntityKind::StructDecl | EntityKind::UnionDecl | EntityKind::EnumDecl
+                )
+            })
+            .filter(|e| e.is_definition())
+            .filter(|e| {
+                let selected = filter.selects_declaration(e);
+                if !selected {
+                    debug!("skipping {e:?}, because it is not declared in a selected file");
+                }
+                selected
+            });
+
+        // Get the typedef names referring to each of the structs, unions and enums
+        let mut typedef_names: HashMap<clang::Entity, Vec<String>> = HashMap::new();
+        for typedef in tu_children
+            .iter()
+            .filter(|e| e.get_kind() == EntityKind::TypedefDecl)
+        {
+            let (Some(typedef_name), Some(type_decl)) = (
+                typedef.get_name(),
+                typedef
+                    .get_typedef_underlying_type()
+                    .and_then(|t| t.get_canonical_type().get_declaration())
+                    .and_then(|d| d.get_definition()),
+            ) else {
+                continue;
+            };
+            typedef_names
+                .entry(type_decl)
+                .or_default()
+                .push(typedef_name);
+        }
+
+        let mut types = Vec::new();
+        let mut anonymous_enums = Vec::new();
+        for type_decl in type_decls {
+            let typedef_names = typedef_names.remove(type_decl).unwrap_or_default();
+
+            // anonymous types are named after the (first) typedef introducing them, every other
+            // typedef is an alias for which the same layout is provided
+            let mut type_names = Vec::new();
+            if !type_decl.is_anonymous() {
+                type_names.extend(type_decl.get_name());
+            }
+            for typedef_name in &typedef_names {
+                if !type_names.contains(typedef_name) {
+                    type_names.push(typedef_name.clone());
+                }
+            }
+
+            // the enumerators of an enum without any name are still usable as constants
+            if type_names.is_empty() && type_decl.get_kind() == EntityKind::EnumDecl {
+                let display_name = type_decl.get_display_name().unwrap_or_default();
+                match gather_enumerators(type_decl) {
+                    Ok((repr, mut enumerators)) => {
+                        enumerators.retain(|enumerator| {
+                            let selected = filter.selects_type(&enumerator.name);
+                            if !selected {
+                                debug!(
+                                    "    skipping enumerator {:?}, because it is not selected",
+                                    enumerator.name
+                                );
+                            }
+                            selected
+                        });
+                        if !enumerators.is_empty() {
+                            anonymous_enums.push(AnonymousEnum { repr, enumerators });
+                        }
+                    }
+                    Err(e) => {
+                        error!(
+                            "skipping {type_decl:?}, because the following error occured while gathering its enumerators:\n{e}"
+                        );
+                        coverage.skip(
+                            SkippedKind::Type,
+                            display_name,
+                            format!("{e:#}"),
+                            SourceLocation::of(type_decl),
+                        );
+                    }
+                }
+            } else if type_names.is_empty() {
+                error!(
+                    "skipping {type_decl:?}, because it neither has a name nor is it named by a typedef"
+                );
+                coverage.skip(
+                    SkippedKind::Type,
+                    type_decl.get_display_name().unwrap_or_default(),
+                    "it neither has a name nor is it named by a typedef",
+                    SourceLocation::of(type_decl),
+                );
+            }
+
+            for type_name in type_names {
+                if !filter.selects_type(&type_name) {
+                    debug!("skipping {type_name:?}, because it is not selected");
+                    continue;
+                }
+
+                let result = match type_decl.get_kind() {
+                    EntityKind::EnumDecl => {
+                        EnumLayout::from_entity(type_decl, &type_name).map(TypeLayout::Enum)
+                    }
+                    _ => RecordLayout::from_entity(
+                        type_decl,
+                        &type_name,
+                        !typedef_names.contains(&type_name),
+                        coverage,
+                    )
+                    .map(TypeLayout::Record),
+                };
+
+                match result {
+                    Ok(TypeLayout::Record(mut record)) => {
+                        record.fields.retain(|field| {
+                            let selected = filter.selects_field(&type_name, field);
+                            if !selected {
+                                debug!(
+                                    "    skipping field {:?}, because it is not selected",
+                                    field.name()
+                                );
+                            }
+                            selected
+                        });
+                        coverage.locate(&type_name, SourceLocation::of(type_decl));
+                        types.push(TypeLayout::Record(record))
+                    }
+                    Ok(type_layout) => {
+                        coverage.locate(&type_name, SourceLocation::of(type_decl));
+                        types.push(type_layout)
+                    }
+                    Err(e) => {
+                        error!(
+                            "skipping to the next type, because the following error occured while gathering its layout:\n{e}"
+                        );
+                        coverage.skip(
+                            SkippedKind::Type,
+                            &type_name,
+                            format!("{e:#}"),
+                            SourceLocation::of(type_decl),
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            target,
+            types,
+            constants: Vec::new(),
+            anonymous_enums,
+        })
+    }
+}
+
+impl RecordLayout {
+    /// Gather the layout of a given struct or union
+    ///
+    /// The `name` is not taken from the `record` itself, because anonymous records are only named
+    /// via a typedef. If `is_tag` is set, the `name` is the record's tag rather than a typedef.
+    /// Skipped fields and the location of each field are recorded in the `coverage`.
+    pub fn from_entity(
+        record: &clang::Entity,
+        name: &str,
+        is_tag: bool,
+        coverage: &mut Coverage,
+    ) -> Result<Self> {
+        let record_type = record.get_type().ok_or_eyre("struct type is unknown?!")?;
+        let size_bytes = record_type.get_sizeof()?.try_into()?;
+        let align_bytes = record_type.get_alignof()?.try_into()?;
+
+        let kind = match record.get_kind() {
+            EntityKind::UnionDecl => RecordKind::Union,
+            _ => RecordKind::Struct,
+        };
+
+        let c_type = match (is_tag, kind) {
+            (false, _) => name.to_owned(),
+            (true, RecordKind::Struct) => format!("struct {name}"),
+            (true, RecordKind::Union) => format!("union {name}"),
+        };
+
+        info!("gathering layout of {kind:?} {name:?}");
+        debug!("{kind:?}: {name:?} (size: {size_bytes} bytes)");
+
+        let mut fields = Vec::new();
+        collect_fields(&mut fields, coverage, name, record_type, &[], 0)?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            c_type,
+            kind,
+            size_bytes,
+            align_bytes,
+            fields,
+        })
+    }
+}
+
+/// Collect the layout of all fields of a record, recursing into fields which are records themselves
+///
+/// # Arguments
+///
+/// - `record_name`: name of the outermost record
+/// - `record_type`: the type of the record whose fields are visited
+/// - `field_path`: names of the fields leading from the outermost record to this record
+/// - `base_offset_bits`: offset of this record from the start of the outermost record
+fn collect_fields(
+    fields: &mut Vec<FieldLayout>,
+    coverage: &mut Coverage,
+    record_name: &str,
+    record_type: clang::Type,
+    field_path: &[String],
+    base_offset_bits: u64,
+) -> Result<()> {
+    let record_fields = record_type
+        .get_fields()
+        .ok_or_eyre("record has no known fields")?;
+
+    for struct_field in record_fields {
+        // unnamed bit-fields only serve as padding, there is nothing to access
+        if struct_field.is_bit_field() && struct_field.get_name().is_none() {
+            debug!("    skipping unnamed bit-field in struct {record_name:?}");
+            continue;
+        }
+
+        // the members of an anonymous struct/union are accessed as if they were members of the
+        // surrounding record
+        if let (None, Some(anonymous_type)) = (
+            struct_field.get_name(),
+            struct_field
+                .get_type()
+                .map(|t| t.get_canonical_type())
+                .filter(|t| t.get_kind() == TypeKind::Record),
+        ) {
+            let anonymous_offset_bits: u64 = struct_field.get_offset_of_field()?.try_into()?;
+            collect_fields(
+                fields,
+                coverage,
+                record_name,
+                anonymous_type,
+                field_path,
+                base_offset_bits + anonymous_offset_bits,
+            )?;
+            continue;
+        }
+
+        // note down the origin of this error
+        let error_origin = format!("struct {record_name:?}, field {struct_field:?}");
+
+        let field_name = struct_field
+            .get_name()
+            .ok_or_eyre("unknown name")
+            .section(error_origin.clone())?;
+
+        // the full name and location of this field, for the coverage
+        let mut qualified_name = record_name.to_owned();
+        for name in field_path.iter().chain([&field_name]) {
+            qualified_name.push('.');
+            qualified_name.push_str(name);
+        }
+        let location = SourceLocation::of(&struct_field);
+
+        // get offset of the field and its type, fail gracefully (by just ignoring the field)
+        let (field_offset_bits, field_ty) = match (
+            record_type.get_offsetof(&field_name),
+            struct_field.get_type(),
+        ) {
+            (Ok(fo), Some(ft)) => (base_offset_bits + u64::try_from(fo)?, ft),
+            (Ok(_), None) => {
+                error!("skipping {error_origin}: it has no known field type");
+                let reason = "it has no known field type";
+                coverage.skip(SkippedKind::Field, qualified_name, reason, location);
+                continue;
+            }
+            (Err(e), Some(_)) => {
+                error!("skipping {error_origin}: getting its offsetof yielded an error:\n{e}");
+                let reason = format!("getting its offsetof yielded an error: {e}");
+                coverage.skip(SkippedKind::Field, qualified_name, reason, location);
+                continue;
+            }
+            (Err(e), None) => {
+                error!(
+                    "skipping {error_origin}: it has no known field type and getting its offsetof yielded an error:\n{e}"
+                );
+                let reason = format!(
+                    "it has no known field type and getting its offsetof yielded an error: {e}"
+                );
+                coverage.skip(SkippedKind::Field, qualified_name, reason, location);
+                continue;
+            }
+        };
+
+        let field_path = [field_path, &[field_name]].concat();
+
+        debug!(
+            "    field: {:?} (offset: {} bits)",
+            field_path.join("."),
+            field_offset_bits
+        );
+
+        let kind = match struct_field.get_bit_field_width() {
+            Some(bit_width) => bit_field_kind(field_offset_bits, bit_width, field_ty),
+            None => field_kind(field_offset_bits, field_ty),
+        };
+
+        let kind_and_align = kind.and_then(|kind| Ok((kind, field_ty.get_alignof()?.try_into()?)));
+
+        let (kind, align_bytes) = match kind_and_align {
+            Ok(kind_and_align) => kind_and_align,
+            Err(e) => {
+                error!(
+                    "gathering the layout of {error_origin} yielded the following error, skipping it\n{e}"
+                );
+                coverage.skip(
+                    SkippedKind::Field,
+                    qualified_name,
+                    format!("{e:#}"),
+                    location,
+                );
+                continue;
+            }
+        };
+        coverage.locate(qualified_name, location);
+
+        let is_record = matches!(kind, FieldKind::Record { .. });
+        fields.push(FieldLayout {
+            path: field_path.clone(),
+            offset_bits: field_offset_bits,
+            align_bytes,
+            kind,
+        });
+
+        // recurse into fields which are records themselves
+        if is_record {
+            collect_fields(
+                fields,
+                coverage,
+                record_name,
+                field_ty.get_canonical_type(),
+                &field_path,
+                field_offset_bits,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Classify a bit-field
+fn bit_field_kind(offset_bits: u64, bit_width: usize, ty: clang::Type) -> Result<FieldKind> {
+    // find a platform agnostic representation of this type, `_Bool` is stored in a byte
+    let canonical_type = ty.get_canonical_type();
+    let repr = match canonical_type.get_kind() {
+        TypeKind::Bool => RepresentableCType::Integer {
+            bytes: 1,
+            is_unsigned: true,
+        },
+        _ => RepresentableCType::new(&canonical_type)?,
+    };
+    let RepresentableCType::Integer { .. } = repr else {
+        bail!("bit-fields of type {repr:?} are not supported");
+    };
+
+    let bit_width: u64 = bit_width.try_into()?;
+    ensure!(
+        (1..=64).contains(&bit_width),
+        "bit-field width of {bit_width} bits is not supported"
+    );
+
+    let window_bytes = (offset_bits % 8 + bit_width).div_ceil(8);
+    ensure!(
+        window_bytes <= 8,
+        "bit-field spans {window_bytes} bytes, at most 8 bytes are supported"
+    );
+
+    Ok(FieldKind::BitField { repr, bit_width })
+}
+
+/// Classify a field which is not a bit-field
+fn field_kind(offset_bits: u64, ty: clang::Type) -> Result<FieldKind> {
+    if !offset_bits.is_multiple_of(8) {
+        bail!("bit offset which is not devisable by 8, this is not implemented yet");
+    }
+
+    // desugar this type so that we know what it actually is
+    let canonical_type = ty.get_canonical_type();
+
+    // find a platform agnostic representation of this type
+    let repr = RepresentableCType::new(&canonical_type)?;
+
+    use TypeKind::*;
+    Ok(match (canonical_type.get_kind(), repr.element_type()) {
+        // integer or float or pointer
+        (
+            CharS | CharU | SChar | UChar | Short | UShort | Int | UInt | Long | ULong | LongLong
+            | ULongLong | Float | Double | Enum | Pointer,
+            RepresentableCType::Integer { .. } | RepresentableCType::Float { .. },
+        ) => {
+            let meaning = match canonical_type.get_kind() {
+                Enum => ScalarMeaning::Enum {
+                    enum_name: canonical_type
+                        .get_declaration()
+                        .filter(|d| !d.is_anonymous())
+                        .and_then(|d| d.get_name())
+                        .unwrap_or_else(|| ty.get_display_name()),
+                },
+                Pointer => {
+                    let pointee_type = ty
+                        .get_pointee_type()
+                        .ok_or_eyre("pointer has no known pointee type")?;
+                    match pointee_type.get_canonical_type().get_kind() {
+                        FunctionPrototype | FunctionNoPrototype => ScalarMeaning::FunctionPointer {
+                            signature: pointee_type.get_display_name(),
+                        },
+                        _ => ScalarMeaning::DataPointer {
+                            pointee_type_name: pointee_type.get_display_name(),
+                        },
+                    }
+                }
+                _ => ScalarMeaning::Value,
+            };
+            FieldKind::Scalar { repr, meaning }
+        }
+
+        // an array of primitive types
+        (ConstantArray, RepresentableCType::Integer { .. } | RepresentableCType::Float { .. }) => {
+            FieldKind::Array { repr }
+        }
+
+        // an array of records
+        (ConstantArray, RepresentableCType::Opaque { bytes: Some(bytes) })
+            if innermost_element_type(canonical_type).get_kind() == Record =>
+        {
+            FieldKind::RecordArray {
+                element_type_name: innermost_element_type(ty).get_display_name(),
+                element_size_bytes: bytes,
+                dimensions: repr.dimensions(),
+            }
+        }
+
+        // a nested record
+        (Record, _) => FieldKind::Record {
+            type_name: ty.get_display_name(),
+            size_bytes: canonical_type.get_sizeof()?.try_into()?,
+        },
+
+        // we don't know what to do
+        (type_kind, _) => FieldKind::Opaque {
+            type_kind: format!("{type_kind:?}"),
+            type_name: ty.get_display_name(),
+            size_bytes: repr.total_size_bytes()?,
+        },
+    })
+}
+
+/// Get the type of the innermost elements of a (possibly multi-dimensional) array, or the type itself
+fn innermost_element_type(ty: clang::Type) -> clang::Type {
+    let mut ty = ty;
+    while let Some(element_type) = ty.get_element_type() {
+        ty = element_type;
+    }
+    ty
+}
+
+impl EnumLayout {
+    /// Gather the enumerators of a given enum
+    ///
+    /// The `name` is not taken from the `enum_` itself, because anonymous enums are only named via
+    /// a typedef.
+    pub fn from_entity(enum_: &clang::Entity, name: &str) -> Result<Self> {
+        info!("gathering layout of enum {name:?}");
+
+        let (repr, enumerators) = gather_enumerators(enum_)?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            repr,
+            enumerators,
+        })
+    }
+}
+
+/// Gather the representation of an enum's underlying type and its enumerators
+fn gather_enumerators(enum_: &clang::Entity) -> Result<(RepresentableCType, Vec<Enumerator>)> {
+    let underlying_type = enum_
+        .get_enum_underlying_type()
+        .ok_or_eyre("enum has no known underlying type")?;
+
+    // find a platform agnostic representation of the underlying type
+    let repr = RepresentableCType::new(&underlying_type.get_canonical_type())?;
+    let RepresentableCType::Integer { .. } = repr else {
+        bail!("enums with an underlying type of {repr:?} are not supported");
+    };
+
+    let mut enumerators = Vec::new();
+    for enumerator in enum_
+        .get_children()
+        .into_iter()
+        .filter(|e| e.get_kind() == EntityKind::EnumConstantDecl)
+    {
+        let enumerator_name = enumerator.get_name().ok_or_eyre("enumerator has no name")?;
+        let (signed_value, unsigned_value) = enumerator
+            .get_enum_constant_value()
+            .ok_or_eyre("enumerator has no known value")?;
+
+        debug!("    enumerator: {enumerator_name:?} (value: {signed_value})");
+
+        enumerators.push(Enumerator {
+            name: enumerator_name,
+            signed_value,
+            unsigned_value,
+        });
+    }
+
+    Ok((repr, enumerators))
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use clang::{EntityKind, EvaluationResult, Index, TranslationUnit, Unsaved};
+use color_eyre::{Result, eyre::OptionExt};
+use log::{debug, info, warn};
+use regex::Regex;
+
+use super::{MacroConstant, RepresentableCType};
+
+/// Prefix of the variables through which macros are evaluated
+const EVALUATION_VARIABLE_PREFIX: &str = "c_abi_lens_macro__";
+
+/// Evaluate the object-like macros defined in `tu` with a name matching any of the `patterns`
+///
+/// The `tu` must have been parsed with a detailed preprocessing record. Each selected macro is
+/// evaluated in a synthetic translation unit, which includes the `input_file` and is parsed with
+/// the same `clang_args` as the `tu`. Hence the value is the one of the macro's final definition on
+/// the foreign target, including any override the guest made via a `-D` flag to a default guarded
+/// by `#ifndef`. Macros defined via `-D` flags in the `clang_args` are considered as well. Macros
+/// which do not evaluate to an integer are skipped with a warning.
+pub fn evaluate_macros(
+    index: &Index,
+    tu: &TranslationUnit,
+    input_file: &Path,
+    clang_args: &[String],
+    patterns: &[Regex],
+) -> Result<Vec<MacroConstant>> {
+    if patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // the names of all selected macros, in order of their first definition
+    let mut macro_names: Vec<String> = Vec::new();
+    for macro_definition in tu
+        .get_entity()
+        .get_children()
+        .into_iter()
+        .filter(|e| e.get_kind() == EntityKind::MacroDefinition)
+        .filter(|e| !e.is_function_like_macro())
+    {
+        let Some(name) = macro_definition.get_name() else {
+            continue;
+        };
+        if macro_names.contains(&name) || !patterns.iter().any(|r| r.is_match(&name)) {
+            continue;
+        }
+
+        // a macro without any tokens besides its name has no value
+        let is_empty = macro_definition
+            .get_range()
+            .is_some_and(|r| r.tokenize().len() <= 1);
+        if is_empty {
+            debug!("skipping macro {name:?}, because it is defined without a value");
+            continue;
+        }
+
+        macro_names.push(name);
+    }
+
+    // macros defined on the command line are not necessarily part of the preprocessing record
+    let mut clang_args_iter = clang_args.iter();
+    while let Some(arg) = clang_args_iter.next() {
+        let definition = match arg.strip_prefix("-D") {
+            Some("") => clang_args_iter.next().map(String::as_str),
+            definition => definition,
+        };
+        let Some(name) = definition.and_then(|d| d.split('=').next()) else {
+            continue;
+        };
+        if !macro_names.iter().any(|n| n == name) && patterns.iter().any(|r| r.is_match(name)) {
+            macro_names.push(name.to_owned());
+        }
+    }
+
+    if macro_names.is_empty() {
+        warn!("no object-like macro matches any of the macro patterns");
+        return Ok(Vec::new());
+    }
+
+    // declare one variable per macro, typed and initialized with the macro's expansion
+    let input_file = input_file.canonicalize()?;
+    let mut synthetic_source = format!(
+        "#include \"{}\"\n",
+        input_file
+            .to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    );
+    for name in &macro_names {
+        synthetic_source.push_str(&format!(
+            "static const __typeof__(({name})) {EVALUATION_VARIABLE_PREFIX}{name} = ({name});\n"
+        ));
+    }
+    debug!("synthetic source for the evaluation of macros:\n{synthetic_source}");
+
+    let synthetic_file = input_file
+        .parent()
+        .ok_or_eyre("input file has no parent directory")?
+        .join("c-abi-lens-macros.c");
+    let mut parser = index.parser(&synthetic_file);
+    parser.arguments(clang_args);
+    parser.unsaved(&[Unsaved::new(&synthetic_file, &synthetic_source)]);
+    let synthetic_tu = parser.parse()?;
+
+    let mut constants = Vec::new();
+    for variable in synthetic_tu
+        .get_entity()
+        .get_children()
+        .into_iter()
+        .filter(|e| e.get_kind() == EntityKind::VarDecl)
+    {
+        let Some(name) = variable.get_name().and_then(|n| {
+            n.strip_prefix(EVALUATION_VARIABLE_PREFIX)
+                .map(str::to_owned)
+        }) else {
+            continue;
+        };
+
+        let repr = variable
+            .get_type()
+            .map(|t| RepresentableCType::new(&t.get_canonical_type()));
+        let (Some(Ok(repr @ RepresentableCType::Integer { .. })), Some(value)) =
+            (repr, variable.evaluate())
+        else {
+            warn!("skipping macro {name:?}, because it does not expand to an integer expression");
+            continue;
+        };
+
+        let (signed_value, unsigned_value) = match value {
+            EvaluationResult::SignedInteger(v) => (v, v as u64),
+            EvaluationResult::UnsignedInteger(v) => (v as i64, v),
+            _ => {
+                warn!("skipping macro {name:?}, because it does not evaluate to an integer");
+                continue;
+            }
+        };
+
+        info!("macro {name:?} evaluates to {signed_value} as {repr}");
+        constants.push(MacroConstant {
+            name,
+            repr,
+            signed_value,
+            unsigned_value,
+        });
+    }
+
+    Ok(constants)
+}
@@ -2,8 +2,26 @@
 
 mod c_types;
 mod function_emitter;
+mod json_emitter;
+mod layout;
+mod macro_constants;
+mod rust_emitter;
+mod self_test_emitter;
+mod static_assert_emitter;
+mod target;
 mod tokens;
+mod type_filter;
+mod wasm_emitter;
 
 pub use c_types::*;
 pub use function_emitter::*;
+pub use json_emitter::*;
+pub use layout::*;
+pub use macro_constants::*;
+pub use rust_emitter::*;
+pub use self_test_emitter::*;
+pub use static_assert_emitter::*;
+pub use target::*;
 pub use tokens::*;
+pub use type_filter::*;
+pub use wasm_emitter::*;
@@ -0,0 +1,1204 @@
+use color_eyre::{Result, eyre::bail};
+use log::{error, info};
+
+use crate::report::{Coverage, SkippedKind};
+
+use super::{
+    AnonymousEnum, Endianness, EnumLayout, FieldKind, FieldLayout, LensLayout, MacroConstant,
+    RecordLayout, RepresentableCType, ScalarMeaning, TypeLayout,
+};
+
+/// Types and helpers shared by all generated accessors
+///
+/// The accessors never panic and contain no unsafe code, every out of bounds access is reported
+/// as [`LensError`].
+const RUST_PRELUDE: &str = "\
+/// Error returned by the accessors if an access can not be performed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LensError {
+    /// The `len` bytes starting at `addr` are not within the given memory
+    OutOfBounds { addr: usize, len: usize },
+
+    /// The `index` into `dimension` (counting from the outermost) of an array field is not less
+    /// than the `length` of that dimension
+    IndexOutOfRange {
+        dimension: usize,
+        index: usize,
+        length: usize,
+    },
+
+    /// An address computation exceeds the host's address space
+    AddressOverflow,
+}
+
+impl core::fmt::Display for LensError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::OutOfBounds { addr, len } => {
+                write!(f, \"access of {len} bytes at address {addr:#x} is out of bounds\")
+            }
+            Self::IndexOutOfRange {
+                dimension,
+                index,
+                length,
+            } => write!(
+                f,
+                \"index {index} is out of range for dimension {dimension} of length {length}\"
+            ),
+            Self::AddressOverflow => f.write_str(\"address computation overflowed\"),
+        }
+    }
+}
+
+/// Compute `base + offset`
+#[allow(dead_code)]
+fn lens_addr(base: usize, offset: usize) -> Result<usize, LensError> {
+    base.checked_add(offset).ok_or(LensError::AddressOverflow)
+}
+
+/// Compute the address of one element of a (possibly multi-dimensional) array in row-major order
+///
+/// `indices` holds the index and the length of each dimension, starting with the outermost.
+#[allow(dead_code)]
+fn lens_element_addr(
+    base: usize,
+    offset: usize,
+    element_size: usize,
+    indices: &[(usize, usize)],
+) -> Result<usize, LensError> {
+    let mut element_index: usize = 0;
+    for (dimension, &(index, length)) in indices.iter().enumerate() {
+        if index >= length {
+            return Err(LensError::IndexOutOfRange {
+                dimension,
+                index,
+                length,
+            });
+        }
+        element_index = element_index * length + index;
+    }
+    let element_offset = element_index
+        .checked_mul(element_size)
+        .and_then(|o| o.checked_add(offset))
+        .ok_or(LensError::AddressOverflow)?;
+    lens_addr(base, element_offset)
+}
+
+/// Read the `N` bytes starting at `addr`
+#[allow(dead_code)]
+fn lens_read<const N: usize>(memory: &[u8], addr: usize) -> Result<[u8; N], LensError> {
+    addr.checked_add(N)
+        .and_then(|end| memory.get(addr..end))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(LensError::OutOfBounds { addr, len: N })
+}
+
+/// Overwrite the bytes starting at `addr` with `bytes`
+#[allow(dead_code)]
+fn lens_write(memory: &mut [u8], addr: usize, bytes: &[u8]) -> Result<(), LensError> {
+    let len = bytes.len();
+    addr.checked_add(len)
+        .and_then(|end| memory.get_mut(addr..end))
+        .ok_or(LensError::OutOfBounds { addr, len })?
+        .copy_from_slice(bytes);
+    Ok(())
+}
+";
+
+/// Representation of a top-level item (function or constant) in Rust
+struct RustItem {
+    comment: String,
+
+    /// Lints to allow on this item, generated names are not idiomatic Rust
+    allow: &'static str,
+    code: String,
+}
+
+impl RustItem {
+    fn function(comment: String, code: String) -> Self {
+        Self {
+            comment,
+            allow: "non_snake_case",
+            code,
+        }
+    }
+
+    /// A function matching on constants
+    fn matcher(comment: String, code: String) -> Self {
+        Self {
+            comment,
+            allow: "non_snake_case, non_upper_case_globals",
+            code,
+        }
+    }
+
+    fn constant(comment: String, code: String) -> Self {
+        Self {
+            comment,
+            allow: "non_upper_case_globals",
+            code,
+        }
+    }
+
+    /// Generate the item's code
+    ///
+    /// # Arguments
+    ///
+    /// - `emit_comment`: whether to also emit a doc-comment explaining the item up-front
+    fn generate(&self, emit_comment: bool) -> String {
+        let mut result = String::new();
+        if emit_comment {
+            for line in self.comment.lines() {
+                result.push_str("///");
+                if !line.is_empty() {
+                    result.push(' ');
+                    result.push_str(line);
+                }
+                result.push('\n');
+            }
+        }
+        result.push_str(&format!("#[allow({})]\n", self.allow));
+        result.push_str(&self.code);
+        result.push('\n');
+        result
+    }
+}
+
+/// Generic representation of a snippet of Rust code
+enum RustSnippet {
+    Item(RustItem),
+    Section(String),
+}
+
+impl From<RustItem> for RustSnippet {
+    fn from(value: RustItem) -> Self {
+        Self::Item(value)
+    }
+}
+
+/// Options steering the emitted Rust module
+#[derive(Debug, Clone)]
+pub struct RustEmitterConfig {
+    /// Wrap everything in a `pub mod` of this name, unless it is empty
+    pub module_name: String,
+
+    /// Whether to emit a doc-comment for each item
+    pub emit_comment: bool,
+
+    /// Whether to emit helpers returning the address held by pointer fields
+    pub emit_deref_helpers: bool,
+}
+
+/// Generate a Rust module providing accessors for all types in `layout`
+///
+/// The generated code is `no_std` compatible and free of unsafe code. Accessors operate on a byte
+/// slice representing the foreign memory (e.g. a Wasm linear memory) and addresses within it. As
+/// the foreign endianness is known from the layout, values are always converted from/to the
+/// foreign byte order, there is no need for an explicit endianness swap.
+///
+/// The `header` is put in front of the generated code as comment. Types and fields for which no
+/// items can be generated are skipped, and recorded in the `coverage`.
+pub fn generate_rust_code(
+    layout: &LensLayout,
+    header: &str,
+    config: &RustEmitterConfig,
+    coverage: &mut Coverage,
+) -> String {
+    let mut snippets: Vec<RustSnippet> = Vec::new();
+
+    for type_layout in &layout.types {
+        let (result, name) = match type_layout {
+            TypeLayout::Record(record) => (
+                insert_record_items(
+                    &mut snippets,
+                    record,
+                    layout.target.endianness,
+                    config,
+                    coverage,
+                ),
+                &record.name,
+            ),
+            TypeLayout::Enum(enum_) => (insert_enum_items(&mut snippets, enum_), &enum_.name),
+        };
+
+        if let Err(e) = result {
+            error!(
+                "skipping to the next type, because the following error occured while generating its functions:\n{e}"
+            );
+            coverage.skip(SkippedKind::Type, name, format!("{e:#}"), None);
+        }
+    }
+
+    if let Err(e) = insert_constant_items(&mut snippets, &layout.constants) {
+        error!(
+            "skipping the constants, because the following error occured while generating them:\n{e}"
+        )
+    }
+
+    if let Err(e) = insert_anonymous_enum_items(&mut snippets, &layout.anonymous_enums) {
+        error!(
+            "skipping the anonymous enums, because the following error occured while generating them:\n{e}"
+        )
+    }
+
+    let mut body = String::from(RUST_PRELUDE);
+    for snippet in &snippets {
+        body.push('\n');
+        match snippet {
+            RustSnippet::Item(item) => body.push_str(&item.generate(config.emit_comment)),
+            RustSnippet::Section(title) => body.push_str(&format!("// {title:*^76}\n")),
+        }
+    }
+
+    let mut result: String = header
+        .lines()
+        .map(|line| format!("//{}{line}\n", if line.is_empty() { "" } else { " " }))
+        .collect();
+    result.push('\n');
+
+    if config.module_name.is_empty() {
+        result.push_str(&body);
+    } else {
+        result.push_str(&format!("pub mod {} {{\n", config.module_name));
+        for line in body.lines() {
+            if !line.is_empty() {
+                result.push_str("    ");
+                result.push_str(line);
+            }
+            result.push('\n');
+        }
+        result.push_str("}\n");
+    }
+
+    result
+}
+
+/// Get the Rust type for a primitive [`RepresentableCType`]
+fn rust_type(repr: &RepresentableCType) -> Result<String> {
+    Ok(match repr {
+        RepresentableCType::Integer { bytes, is_unsigned } => {
+            let bits = u16::from(*bytes) * 8;
+            format!("{}{bits}", if *is_unsigned { "u" } else { "i" })
+        }
+        RepresentableCType::Float { bytes: 4 } => "f32".to_owned(),
+        RepresentableCType::Float { bytes: 8 } => "f64".to_owned(),
+        RepresentableCType::Array { .. } => {
+            let mut result = rust_type(&repr.element_type())?;
+            for length in repr.dimensions().iter().rev() {
+                result = format!("[{result}; {length}]");
+            }
+            result
+        }
+        RepresentableCType::UIntPtr => "usize".to_owned(),
+        RepresentableCType::Bool => "bool".to_owned(),
+        RepresentableCType::Float { .. }
+        | RepresentableCType::Opaque { .. }
+        | RepresentableCType::ConstCharPtr
+        | RepresentableCType::Void => bail!("type {repr:?} has no Rust representation"),
+    })
+}
+
+/// Name of the conversion functions for the foreign byte order, e.g. `le` for `from_le_bytes`
+fn byte_order(endianness: Endianness) -> &'static str {
+    match endianness {
+        Endianness::Little => "le",
+        Endianness::Big => "be",
+    }
+}
+
+/// Insert the items for a given struct or union and all its fields
+fn insert_record_items(
+    snippets: &mut Vec<RustSnippet>,
+    struct_: &RecordLayout,
+    endianness: Endianness,
+    config: &RustEmitterConfig,
+    coverage: &mut Coverage,
+) -> Result<()> {
+    let struct_name = &struct_.name;
+    let struct_size_bytes = struct_.size_bytes;
+
+    info!("generating Rust for {:?} {struct_name:?}", struct_.kind);
+
+    snippets.push(RustSnippet::Section(format!(" {struct_name} ")));
+
+    snippets.push(
+        RustItem::function(
+            format!(
+                "\
+                `size_of::<{struct_name}>()`\n\
+                \n\
+                Returns the size in bytes consumed for one instance of the `{struct_name}`\
+                "
+            ),
+            format!(
+                "pub const fn sizeof__{struct_name}() -> usize {{\n    {struct_size_bytes}\n}}"
+            ),
+        )
+        .into(),
+    );
+
+    snippets.push(
+        RustItem::function(
+            format!(
+                "\
+                `&array_base_addr[i]`\n\
+                \n\
+                Returns the base address of the `i`-th `{struct_name}` in a contiguous array of them, using the foreign size of {struct_size_bytes} bytes per element\
+                "
+            ),
+            format!(
+                "\
+pub fn index__{struct_name}(array_base_addr: usize, i: usize) -> Result<usize, LensError> {{
+    let offset = i
+        .checked_mul({struct_size_bytes})
+        .ok_or(LensError::AddressOverflow)?;
+    lens_addr(array_base_addr, offset)
+}}"
+            ),
+        )
+        .into(),
+    );
+
+    for field in &struct_.fields {
+        if let Err(e) = insert_field_items(snippets, struct_name, field, endianness, config) {
+            error!(
+                "generating the per-field functions for struct {struct_name:?}, field {:?} yielded the following error, skipping it\n{e}",
+                field.name()
+            );
+            let qualified_name = format!("{struct_name}.{}", field.name());
+            coverage.skip(SkippedKind::Field, qualified_name, format!("{e:#}"), None);
+        }
+    }
+
+    Ok(())
+}
+
+/// Insert the items for a given struct's field
+fn insert_field_items(
+    snippets: &mut Vec<RustSnippet>,
+    struct_name: &str,
+    field: &FieldLayout,
+    endianness: Endianness,
+    config: &RustEmitterConfig,
+) -> Result<()> {
+    let field_name = field.name();
+    let function_name_gen = |op| format!("{op}__{struct_name}__{}", field.identifier());
+    let offset_bytes = field.offset_bytes();
+    let size_bytes = field.size_bytes()?;
+    let order = byte_order(endianness);
+
+    let mut items = Vec::new();
+
+    let (size_comment, offset_comment) = match field.kind {
+        FieldKind::BitField { .. } => (
+            format!(
+                "\
+                Bytes spanned by `{struct_name}.{field_name}`\n\
+                \n\
+                Returns the number of bytes touched by the `{field_name}` bit-field from the `{struct_name}` struct\
+                "
+            ),
+            format!(
+                "\
+                Byte offset of `{struct_name}.{field_name}`\n\
+                \n\
+                Get the offset in bytes of the first byte touched by the `{field_name}` bit-field from the start of a `{struct_name}` struct\
+                "
+            ),
+        ),
+        _ => (
+            format!(
+                "\
+                `size_of_val(&{struct_name}.{field_name})`\n\
+                \n\
+                Returns the size in bytes of the `{field_name}` field from the `{struct_name}` struct\
+                "
+            ),
+            format!(
+                "\
+                `offset_of!({struct_name}, {field_name})`\n\
+                \n\
+                Get the offset in bytes of the `{field_name}` field from the start of a `{struct_name}` struct\
+                "
+            ),
+        ),
+    };
+
+    items.push(RustItem::function(
+        size_comment,
+        format!(
+            "pub const fn {}() -> usize {{\n    {size_bytes}\n}}",
+            function_name_gen("sizeof")
+        ),
+    ));
+    items.push(RustItem::function(
+        offset_comment,
+        format!(
+            "pub const fn {}() -> usize {{\n    {offset_bytes}\n}}",
+            function_name_gen("offsetof")
+        ),
+    ));
+
+    // accessor for the address of a field, used wherever no value can be represented
+    let address_item = |comment: String| {
+        RustItem::function(
+            comment,
+            format!(
+                "\
+pub fn {}(struct_base_addr: usize) -> Result<usize, LensError> {{
+    lens_addr(struct_base_addr, {offset_bytes})
+}}",
+                function_name_gen("get")
+            ),
+        )
+    };
+
+    match &field.kind {
+        // integer or float or pointer
+        FieldKind::Scalar { repr, meaning } => {
+            let ty = rust_type(repr)?;
+
+            // string to explain the value of function pointers and enums
+            let maybe_value_note = match meaning {
+                ScalarMeaning::FunctionPointer { signature } => format!(
+                    "\n\
+                    \n\
+                    The field is a pointer to a function of signature `{signature}`. On Wasm targets its value\n\
+                    is the index of that function in the function table (exported via `--export-table`)\
+                    "
+                ),
+                ScalarMeaning::Enum { enum_name } => format!(
+                    "\n\
+                    \n\
+                    The field is of type `enum {enum_name}`. Values outside of the declared enumerators are passed\n\
+                    through unaltered, use `is_valid__{enum_name}` to check and `to_string__{enum_name}` to name a value\
+                    "
+                ),
+                ScalarMeaning::Value | ScalarMeaning::DataPointer { .. } => String::default(),
+            };
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Get `{struct_name}.{field_name}`\n\
+                    \n\
+                    Returns the field `{field_name}`'s value from an instance of the `{struct_name}` struct{maybe_value_note}\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &[u8], struct_base_addr: usize) -> Result<{ty}, LensError> {{
+    let addr = lens_addr(struct_base_addr, {offset_bytes})?;
+    Ok({ty}::from_{order}_bytes(lens_read(memory, addr)?))
+}}",
+                    function_name_gen("get")
+                ),
+            ));
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Set `{struct_name}.{field_name}` to `value`\n\
+                    \n\
+                    Overwrites the field `{field_name}`'s value of an `{struct_name}` struct instance with `value`{maybe_value_note}\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &mut [u8], struct_base_addr: usize, value: {ty}) -> Result<(), LensError> {{
+    let addr = lens_addr(struct_base_addr, {offset_bytes})?;
+    lens_write(memory, addr, &value.to_{order}_bytes())
+}}",
+                    function_name_gen("set")
+                ),
+            ));
+
+            // the guest address held by a data pointer, function pointers do not point into the memory
+            if let (ScalarMeaning::DataPointer { pointee_type_name }, true) =
+                (meaning, config.emit_deref_helpers)
+            {
+                items.push(RustItem::function(
+                    format!(
+                        "\
+                        Dereference `{struct_name}.{field_name}`\n\
+                        \n\
+                        Returns the address of the `{pointee_type_name}` the field `{field_name}` of an `{struct_name}` struct instance points to\n\
+                        \n\
+                        The returned address can be passed as `struct_base_addr` to the accessors of the pointee. No null check is performed.\
+                        "
+                    ),
+                    format!(
+                        "\
+pub fn {}(memory: &[u8], struct_base_addr: usize) -> Result<usize, LensError> {{
+    usize::try_from({}(memory, struct_base_addr)?).map_err(|_| LensError::AddressOverflow)
+}}",
+                        function_name_gen("deref"),
+                        function_name_gen("get")
+                    ),
+                ));
+            }
+        }
+
+        // integer bit-field
+        FieldKind::BitField { repr, bit_width } => {
+            let ty = rust_type(repr)?;
+            let RepresentableCType::Integer { is_unsigned, .. } = repr else {
+                bail!("bit-fields of type {repr:?} are not supported");
+            };
+
+            let offset_bits = field.offset_bits;
+            let bit_offset_in_window = offset_bits % 8;
+            let window_bytes = size_bytes;
+
+            // where the spanned bytes go within an u64 and where the bit-field is within them
+            let (window_range, shift) = match endianness {
+                Endianness::Little => (format!("..{window_bytes}"), bit_offset_in_window),
+                Endianness::Big => (
+                    format!("{}..", 8 - window_bytes),
+                    window_bytes * 8 - bit_offset_in_window - bit_width,
+                ),
+            };
+            let mask: u64 = u64::MAX >> (64 - bit_width);
+            let sign_bit: u64 = 1 << (bit_width - 1);
+
+            // Rust code string that loads the spanned bytes into `window`
+            let load_window = format!(
+                "    let addr = lens_addr(struct_base_addr, {offset_bytes})?;
+    let mut window = [0u8; 8];
+    window[{window_range}].copy_from_slice(&lens_read::<{window_bytes}>(memory, addr)?);
+    let window = u64::from_{order}_bytes(window);"
+            );
+
+            let maybe_sign_extend = if *is_unsigned {
+                String::default()
+            } else {
+                format!("\n    let value = (value ^ {sign_bit:#x}).wrapping_sub({sign_bit:#x});")
+            };
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Bit offset of `{struct_name}.{field_name}`\n\
+                    \n\
+                    Get the offset in bits of the `{field_name}` bit-field from the start of a `{struct_name}` struct, as laid out by the foreign target\
+                    "
+                ),
+                format!(
+                    "pub const fn {}() -> usize {{\n    {offset_bits}\n}}",
+                    function_name_gen("bitoffsetof")
+                ),
+            ));
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Bit width of `{struct_name}.{field_name}`\n\
+                    \n\
+                    Get the width in bits of the `{field_name}` bit-field from the `{struct_name}` struct\
+                    "
+                ),
+                format!(
+                    "pub const fn {}() -> usize {{\n    {bit_width}\n}}",
+                    function_name_gen("bitwidthof")
+                ),
+            ));
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Get `{struct_name}.{field_name}`\n\
+                    \n\
+                    Returns the bit-field `{field_name}`'s value from an instance of the `{struct_name}` struct\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &[u8], struct_base_addr: usize) -> Result<{ty}, LensError> {{
+{load_window}
+    let value = (window >> {shift}) & {mask:#x};{maybe_sign_extend}
+    Ok(value as {ty})
+}}",
+                    function_name_gen("get")
+                ),
+            ));
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Set `{struct_name}.{field_name}` to `value`\n\
+                    \n\
+                    Overwrites the bit-field `{field_name}`'s value of an `{struct_name}` struct instance with `value`\n\
+                    \n\
+                    Only the lower {bit_width} bits of `value` are stored, all other bits spanned by the bit-field's bytes are preserved\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &mut [u8], struct_base_addr: usize, value: {ty}) -> Result<(), LensError> {{
+{load_window}
+    let window = (window & !{:#x}) | ((value as u64 & {mask:#x}) << {shift});
+    lens_write(memory, addr, &window.to_{order}_bytes()[{window_range}])
+}}",
+                    function_name_gen("set"),
+                    mask << shift
+                ),
+            ));
+        }
+
+        // an array of primitive types
+        FieldKind::Array { repr } => {
+            let ty = rust_type(repr)?;
+            let element_ty = rust_type(&repr.element_type())?;
+            let element_bytes = repr.element_size_bytes()?;
+            let dimensions = repr.dimensions();
+
+            items.extend(array_length_items(
+                struct_name,
+                &field_name,
+                &function_name_gen("length"),
+                &dimensions,
+            ));
+
+            let ArrayIndexing {
+                index_arguments,
+                index_names,
+                index_subscripts,
+                element_addr_expr,
+            } = ArrayIndexing::new(&dimensions, offset_bytes, element_bytes);
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Get `{struct_name}.{field_name}{index_subscripts}`\n\
+                    \n\
+                    Returns one element of the `{field_name}` array field from an instance of the `{struct_name}` struct\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &[u8], struct_base_addr: usize, {index_arguments}) -> Result<{element_ty}, LensError> {{
+    let addr = {element_addr_expr}?;
+    Ok({element_ty}::from_{order}_bytes(lens_read(memory, addr)?))
+}}",
+                    function_name_gen("get_at")
+                ),
+            ));
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Set `{struct_name}.{field_name}{index_subscripts}` to `value`\n\
+                    \n\
+                    Overwrites one element of the `{field_name}` array field of an `{struct_name}` struct instance with `value`\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &mut [u8], struct_base_addr: usize, {index_arguments}, value: {element_ty}) -> Result<(), LensError> {{
+    let addr = {element_addr_expr}?;
+    lens_write(memory, addr, &value.to_{order}_bytes())
+}}",
+                    function_name_gen("set_at")
+                ),
+            ));
+
+            // loops over all elements, binding each index and the element to `value`
+            let nested_loops = |iter_fn: &str, statement: &str| {
+                let mut code = String::new();
+                for (depth, index_name) in index_names.iter().enumerate() {
+                    let indentation = "    ".repeat(depth + 1);
+                    code.push_str(&format!(
+                        "{indentation}for ({index_name}, value) in value.{iter_fn}().enumerate() {{\n"
+                    ));
+                }
+                let indentation = "    ".repeat(index_names.len() + 1);
+                code.push_str(&format!("{indentation}{statement}"));
+                for depth in (0..index_names.len()).rev() {
+                    let indentation = "    ".repeat(depth + 1);
+                    code.push_str(&format!("\n{indentation}}}"));
+                }
+                code
+            };
+            let indices = index_names.join(", ");
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Read from `{struct_name}.{field_name}`\n\
+                    \n\
+                    Returns a copy of the `{field_name}` field of an instance of the `{struct_name}` struct\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &[u8], struct_base_addr: usize) -> Result<{ty}, LensError> {{
+    let mut value = {};
+{}
+    Ok(value)
+}}",
+                    function_name_gen("read"),
+                    default_array_literal(&element_ty, &dimensions),
+                    nested_loops(
+                        "iter_mut",
+                        &format!(
+                            "*value = {}(memory, struct_base_addr, {indices})?;",
+                            function_name_gen("get_at")
+                        )
+                    )
+                ),
+            ));
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Write to `{struct_name}.{field_name}`\n\
+                    \n\
+                    Copies from `value` to the `{field_name}` field of an `{struct_name}` struct instance\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(memory: &mut [u8], struct_base_addr: usize, value: &{ty}) -> Result<(), LensError> {{
+{}
+    Ok(())
+}}",
+                    function_name_gen("write"),
+                    nested_loops(
+                        "iter",
+                        &format!(
+                            "{}(memory, struct_base_addr, {indices}, *value)?;",
+                            function_name_gen("set_at")
+                        )
+                    )
+                ),
+            ));
+        }
+
+        // an array of records, the element's struct accessors are reused on each element
+        FieldKind::RecordArray {
+            element_type_name: element_name,
+            element_size_bytes: element_bytes,
+            dimensions,
+        } => {
+            items.push(address_item(format!(
+                "\
+                Get the address of `{struct_name}.{field_name}`\n\
+                \n\
+                This field is an array of `{element_name}`, its elements are accessible via the `{}` function\
+                ",
+                function_name_gen("at")
+            )));
+
+            items.extend(array_length_items(
+                struct_name,
+                &field_name,
+                &function_name_gen("length"),
+                dimensions,
+            ));
+
+            let ArrayIndexing {
+                index_arguments,
+                index_subscripts,
+                element_addr_expr,
+                ..
+            } = ArrayIndexing::new(dimensions, offset_bytes, *element_bytes);
+
+            items.push(RustItem::function(
+                format!(
+                    "\
+                    Get the address of `{struct_name}.{field_name}{index_subscripts}`\n\
+                    \n\
+                    Returns the base address of one `{element_name}` element of the `{field_name}` array field, using the foreign element size of {element_bytes} bytes.\n\
+                    The returned address can be passed as `struct_base_addr` to the accessors of `{element_name}`.\
+                    "
+                ),
+                format!(
+                    "\
+pub fn {}(struct_base_addr: usize, {index_arguments}) -> Result<usize, LensError> {{
+    {element_addr_expr}
+}}",
+                    function_name_gen("at")
+                ),
+            ));
+        }
+
+        // a nested record, its members get their own accessors
+        FieldKind::Record { type_name, .. } => {
+            items.push(address_item(format!(
+                "\
+                Get the address of `{struct_name}.{field_name}`\n\
+                \n\
+                This field is a `{type_name}` itself, its members are accessible via the `{}__<member>` functions\
+                ",
+                function_name_gen("get")
+            )));
+        }
+
+        // we don't know what to do, so just hand out the address
+        FieldKind::Opaque {
+            type_kind,
+            type_name,
+            ..
+        } => {
+            items.push(address_item(format!(
+                "\
+                Get the address of `{struct_name}.{field_name}`\n\
+                \n\
+                No ABI compatible representation of this type is known, therefore this just returns its address\n\
+                \n\
+                type kind: {type_kind}\n\
+                type:      {type_name}\
+                "
+            )));
+        }
+    }
+
+    snippets.push(RustSnippet::Section(format!(
+        " {struct_name}.{field_name} "
+    )));
+    snippets.extend(items.into_iter().map(RustSnippet::from));
+
+    Ok(())
+}
+
+/// A Rust expression for an array of the given dimensions filled with zeros
+fn default_array_literal(element_ty: &str, dimensions: &[u64]) -> String {
+    let zero = if element_ty.starts_with('f') {
+        format!("0.0{element_ty}")
+    } else {
+        format!("0{element_ty}")
+    };
+    dimensions
+        .iter()
+        .rev()
+        .fold(zero, |inner, length| format!("[{inner}; {length}]"))
+}
+
+/// Create the items returning the length of each dimension of an array field
+fn array_length_items(
+    struct_name: &str,
+    field_name: &str,
+    length_function_name: &str,
+    dimensions: &[u64],
+) -> Vec<RustItem> {
+    dimensions
+        .iter()
+        .enumerate()
+        .map(|(dimension, length)| {
+            RustItem::function(
+                format!(
+                    "\
+                    Length of dimension {dimension} of `{struct_name}.{field_name}`\n\
+                    \n\
+                    Returns the number of elements in dimension {dimension} (counting from the outermost) of the `{field_name}` array field\
+                    "
+                ),
+                format!(
+                    "pub const fn {length_function_name}__dim{dimension}() -> usize {{\n    {length}\n}}"
+                ),
+            )
+        })
+        .collect()
+}
+
+/// The pieces required to address one element of a (possibly multi-dimensional) array field
+struct ArrayIndexing {
+    /// One index argument per dimension, as argument list
+    index_arguments: String,
+
+    /// The names of the index arguments
+    index_names: Vec<String>,
+
+    /// The index arguments as subscripts, e.g. `[i0][i1]`
+    index_subscripts: String,
+
+    /// Rust expression for the bounds checked address of the indexed element
+    element_addr_expr: String,
+}
+
+impl ArrayIndexing {
+    fn new(dimensions: &[u64], offset_bytes: u64, element_bytes: u64) -> Self {
+        let index_names: Vec<_> = (0..dimensions.len())
+            .map(|dimension| format!("i{dimension}"))
+            .collect();
+        let index_arguments = index_names
+            .iter()
+            .map(|name| format!("{name}: usize"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let index_subscripts = index_names.iter().map(|name| format!("[{name}]")).collect();
+        let indices = index_names
+            .iter()
+            .zip(dimensions)
+            .map(|(name, length)| format!("({name}, {length})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let element_addr_expr = format!(
+            "lens_element_addr(struct_base_addr, {offset_bytes}, {element_bytes}, &[{indices}])"
+        );
+
+        Self {
+            index_arguments,
+            index_names,
+            index_subscripts,
+            element_addr_expr,
+        }
+    }
+}
+
+/// Insert the constants and functions for a given enum
+fn insert_enum_items(snippets: &mut Vec<RustSnippet>, enum_: &EnumLayout) -> Result<()> {
+    let enum_name = &enum_.name;
+    let ty = rust_type(&enum_.repr)?;
+    let RepresentableCType::Integer { bytes, is_unsigned } = enum_.repr else {
+        bail!(
+            "enums with an underlying type of {:?} are not supported",
+            enum_.repr
+        );
+    };
+
+    info!("generating Rust for enum {enum_name:?}");
+
+    snippets.push(RustSnippet::Section(format!(" enum {enum_name} ")));
+
+    // the constant name for each distinct value, the first enumerator of a value wins
+    let mut distinct_values: Vec<(u64, &str, String)> = Vec::new();
+
+    for enumerator in &enum_.enumerators {
+        let enumerator_name = &enumerator.name;
+        let constant_name = format!("enum__{enum_name}__{enumerator_name}");
+        // same as the C cast, a value is truncated to the width of the enum
+        let value = if is_unsigned {
+            (enumerator.unsigned_value & (u64::MAX >> (64 - u32::from(bytes) * 8))).to_string()
+        } else {
+            enumerator.signed_value.to_string()
+        };
+
+        snippets.push(
+            RustItem::constant(
+                format!(
+                    "\
+                    `{enumerator_name}`\n\
+                    \n\
+                    Value of the `{enumerator_name}` enumerator of `enum {enum_name}` on the foreign architecture\
+                    "
+                ),
+                format!("pub const {constant_name}: {ty} = {value};"),
+            )
+            .into(),
+        );
+
+        if !distinct_values
+            .iter()
+            .any(|(v, ..)| *v == enumerator.unsigned_value)
+        {
+            distinct_values.push((enumerator.unsigned_value, enumerator_name, constant_name));
+        }
+    }
+
+    // match over all distinct values, `arm` yields the expression for each value
+    let match_gen = |arm: &dyn Fn(&str) -> String, default_arm: &str| {
+        if distinct_values.is_empty() {
+            return format!("    let _ = value;\n    {default_arm}");
+        }
+        let mut body = String::from("    match value {\n");
+        for (_, enumerator_name, constant_name) in &distinct_values {
+            body.push_str(&format!(
+                "        {constant_name} => {},\n",
+                arm(enumerator_name)
+            ));
+        }
+        body.push_str(&format!("        _ => {default_arm},\n    }}"));
+        body
+    };
+
+    let is_valid_body = if distinct_values.is_empty() {
+        match_gen(&|_| String::default(), "false")
+    } else {
+        let patterns: Vec<_> = distinct_values.iter().map(|(.., c)| c.as_str()).collect();
+        format!("    matches!(value, {})", patterns.join(" | "))
+    };
+
+    snippets.push(
+        RustItem::matcher(
+            format!(
+                "\
+                Check whether `value` is an `enum {enum_name}`\n\
+                \n\
+                Returns true if `value` equals any of the enumerators declared for `enum {enum_name}`\
+                "
+            ),
+            format!(
+                "pub fn is_valid__{enum_name}(value: {ty}) -> bool {{\n{}\n}}",
+                is_valid_body
+            ),
+        )
+        .into(),
+    );
+
+    snippets.push(
+        RustItem::matcher(
+            format!(
+                "\
+                Name `value` as `enum {enum_name}`\n\
+                \n\
+                Returns the name of the (first) enumerator of `enum {enum_name}` equal to `value`, or `None` if there is none\
+                "
+            ),
+            format!(
+                "pub fn to_string__{enum_name}(value: {ty}) -> Option<&'static str> {{\n{}\n}}",
+                match_gen(&|name| format!("Some(\"{name}\")"), "None")
+            ),
+        )
+        .into(),
+    );
+
+    Ok(())
+}
+
+/// Insert a constant for each evaluated macro
+fn insert_constant_items(
+    snippets: &mut Vec<RustSnippet>,
+    constants: &[MacroConstant],
+) -> Result<()> {
+    if constants.is_empty() {
+        return Ok(());
+    }
+
+    info!("generating Rust for {} macros", constants.len());
+
+    snippets.push(RustSnippet::Section(" constants ".to_owned()));
+
+    for MacroConstant {
+        name,
+        repr,
+        signed_value,
+        unsigned_value,
+    } in constants
+    {
+        let ty = rust_type(repr)?;
+        let value = match repr {
+            RepresentableCType::Integer {
+                is_unsigned: true, ..
+            } => unsigned_value.to_string(),
+            _ => signed_value.to_string(),
+        };
+
+        snippets.push(
+            RustItem::constant(
+                format!(
+                    "\
+                    `{name}`\n\
+                    \n\
+                    Value of the `{name}` macro on the foreign architecture\
+                    "
+                ),
+                format!("pub const {name}: {ty} = {value};"),
+            )
+            .into(),
+        );
+    }
+
+    Ok(())
+}
+
+fn insert_anonymous_enum_items(
+    snippets: &mut Vec<RustSnippet>,
+    anonymous_enums: &[AnonymousEnum],
+) -> Result<()> {
+    if anonymous_enums.is_empty() {
+        return Ok(());
+    }
+
+    info!(
+        "generating Rust for {} anonymous enums",
+        anonymous_enums.len()
+    );
+
+    snippets.push(RustSnippet::Section(" anonymous enums ".to_owned()));
+
+    for AnonymousEnum { repr, enumerators } in anonymous_enums {
+        let ty = rust_type(repr)?;
+        let RepresentableCType::Integer { bytes, is_unsigned } = *repr else {
+            bail!("enums with an underlying type of {repr:?} are not supported");
+        };
+
+        for enumerator in enumerators {
+            let enumerator_name = &enumerator.name;
+            // same as the C cast, a value is truncated to the width of the enum
+            let value = if is_unsigned {
+                (enumerator.unsigned_value & (u64::MAX >> (64 - u32::from(bytes) * 8))).to_string()
+            } else {
+                enumerator.signed_value.to_string()
+            };
+
+            snippets.push(
+                RustItem::constant(
+                    format!(
+                        "\
+                        `{enumerator_name}`\n\
+                        \n\
+                        Value of the `{enumerator_name}` enumerator of an anonymous enum on the foreign architecture\
+                        "
+                    ),
+                    format!("pub const enum__{enumerator_name}: {ty} = {value};"),
+                )
+                .into(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RepresentableCType, default_array_literal, rust_type};
+
+    #[test]
+    fn test_rust_type_primitives() {
+        let cases = [
+            (
+                RepresentableCType::Integer {
+                    bytes: 1,
+                    is_unsigned: true,
+                },
+                "u8",
+            ),
+            (
+                RepresentableCType::Integer {
+                    bytes: 8,
+                    is_unsigned: false,
+                },
+                "i64",
+            ),
+            (RepresentableCType::Float { bytes: 4 }, "f32"),
+            (RepresentableCType::Float { bytes: 8 }, "f64"),
+            (RepresentableCType::UIntPtr, "usize"),
+            (RepresentableCType::Bool, "bool"),
+        ];
+
+        for (repr, expected) in cases {
+            assert_eq!(rust_type(&repr).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_rust_type_array() {
+        let arr = RepresentableCType::Array {
+            element_type: Box::new(RepresentableCType::Array {
+                element_type: Box::new(RepresentableCType::Float { bytes: 4 }),
+                length: 11,
+            }),
+            length: 7,
+        };
+
+        assert_eq!(rust_type(&arr).unwrap(), "[[f32; 11]; 7]");
+        assert_eq!(default_array_literal("f32", &[7, 11]), "[[0.0f32; 11]; 7]");
+    }
+
+    #[test]
+    fn test_rust_type_opaque() {
+        assert!(rust_type(&RepresentableCType::Opaque { bytes: Some(3) }).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use color_eyre::Result;
+use log::{debug, info};
+
+use super::{CFunc, FieldKind, FieldLayout, RecordLayout, RepresentableCType};
+
+/// A field whose accessors are exercised by the self-test
+struct TestedField<'a> {
+    field: &'a FieldLayout,
+
+    /// Type of the value passed to the setter, or to the element setter for arrays
+    value_repr: RepresentableCType,
+
+    /// Dimensions of an array field, empty for scalars and bit-fields
+    dimensions: Vec<u64>,
+
+    /// Bits of the record occupied by this field, as a half-open range
+    bits: (u64, u64),
+
+    /// Seed of the bit pattern written to this field
+    seed: u64,
+}
+
+/// Emit a function checking the accessors of a given struct or union on a buffer in host memory
+///
+/// The function is called `self_test__{name}` and returns the number of failed checks, each of
+/// which is reported on `stderr`. It calls the accessors by their name with the given `prefix`
+/// applied, so it can be appended to the prefixed accessors in one standalone C program. Records of
+/// size zero have no testable fields, for those `None` is returned.
+///
+/// # Checks
+///
+/// - the size returned by `sizeof__{name}` matches the buffer's size
+/// - each field's value round trips through its setter and getter, on top of a buffer of all
+///   zeros as well as all ones
+/// - after setting all non-overlapping fields to distinct bit patterns, each of them still holds
+///   its own pattern, i.e. no setter clobbered any of its neighbours
+pub fn self_test_function(struct_: &RecordLayout, prefix: &str) -> Result<Option<CFunc>> {
+    let struct_name = &struct_.name;
+    let size_bytes = struct_.size_bytes;
+
+    if size_bytes == 0 {
+        debug!("skipping self-test for {struct_name:?}, because it has a size of zero");
+        return Ok(None);
+    }
+
+    info!(
+        "generating self-test for {:?} {struct_name:?}",
+        struct_.kind
+    );
+
+    let prefix = match prefix.is_empty() {
+        true => String::default(),
+        false => format!("{prefix}_"),
+    };
+
+    let mut tested_fields = Vec::new();
+    for (seed, field) in (1..).zip(&struct_.fields) {
+        match tested_field(field, seed)? {
+            Some(tested_field) => tested_fields.push(tested_field),
+            None => debug!(
+                "    field {:?} has no testable accessors, skipping it",
+                field.name()
+            ),
+        }
+    }
+
+    let mut body = format!(
+        "\
+        uint8_t buf[{size_bytes}];\n\
+        uint32_t failures = 0;\n\
+        \n\
+        if ({prefix}sizeof__{struct_name}() != sizeof(buf)) {{\n\
+        \tfprintf(stderr, \"{struct_name}: size mismatch\\n\");\n\
+        \tfailures++;\n\
+        }}\n\
+        \n\
+        /* each field on its own, on top of all zeros and all ones */\n\
+        for (uint8_t background = 0; background < 2; background++) {{\n\
+        "
+    );
+
+    for tested_field in &tested_fields {
+        let accesses = [
+            "memset(buf, background ? 0xff : 0x00, sizeof(buf));".to_owned(),
+            set_code(tested_field, struct_name, &prefix),
+            check_code(tested_field, struct_name, &prefix, "round trip failed"),
+        ];
+        for line in accesses.join("\n").lines() {
+            body.push_str(&format!("\t{line}\n"));
+        }
+    }
+    body.push_str("}\n");
+
+    // greedily pick fields which do not overlap any previously picked one
+    let mut neighbours: Vec<&TestedField> = Vec::new();
+    for tested_field in &tested_fields {
+        let (begin, end) = tested_field.bits;
+        if neighbours
+            .iter()
+            .all(|n| end <= n.bits.0 || n.bits.1 <= begin)
+        {
+            neighbours.push(tested_field);
+        }
+    }
+
+    body.push_str(
+        "\n\
+        /* all non-overlapping fields at once, no setter may clobber any neighbour */\n\
+        memset(buf, 0xa5, sizeof(buf));\n\
+        ",
+    );
+    for tested_field in &neighbours {
+        body.push_str(&set_code(tested_field, struct_name, &prefix));
+        body.push('\n');
+    }
+    for tested_field in &neighbours {
+        body.push_str(&check_code(
+            tested_field,
+            struct_name,
+            &prefix,
+            "clobbered by a neighbour",
+        ));
+        body.push('\n');
+    }
+
+    body.push_str("\nreturn failures;");
+
+    Ok(Some(CFunc {
+        comment: format!(
+            "\
+            Check the accessors of `{struct_name}`\n\
+            \n\
+            Returns the number of failed checks, each of which is reported on `stderr`\
+            "
+        ),
+        return_type: RepresentableCType::Integer {
+            bytes: 4,
+            is_unsigned: true,
+        },
+        name: format!("self_test__{struct_name}"),
+        arguments: vec![],
+        body,
+    }))
+}
+
+/// Determine whether the accessors of a field can be tested, and how
+fn tested_field(field: &FieldLayout, seed: u64) -> Result<Option<TestedField<'_>>> {
+    let (value_repr, dimensions, bit_width) = match &field.kind {
+        FieldKind::Scalar { repr, .. } => (repr.clone(), vec![], repr.total_size_bytes()? * 8),
+        FieldKind::BitField { repr, bit_width } => {
+            // the window spanned by the bit-field must fit into the largest integer
+            if field.size_bytes()?.next_power_of_two() > 8 {
+                return Ok(None);
+            }
+            (repr.clone(), vec![], *bit_width)
+        }
+        FieldKind::Array { repr } => (
+            repr.element_type(),
+            repr.dimensions(),
+            repr.total_size_bytes()? * 8,
+        ),
+        FieldKind::RecordArray { .. } | FieldKind::Record { .. } | FieldKind::Opaque { .. } => {
+            return Ok(None);
+        }
+    };
+
+    // only values with accessors for them are tested
+    let testable = match value_repr {
+        RepresentableCType::Integer { bytes, .. } => matches!(bytes, 1 | 2 | 4 | 8),
+        RepresentableCType::Float { bytes } => matches!(bytes, 4 | 8),
+        _ => false,
+    };
+    if !testable || dimensions.contains(&0) {
+        return Ok(None);
+    }
+
+    Ok(Some(TestedField {
+        field,
+        value_repr,
+        dimensions,
+        bits: (field.offset_bits, field.offset_bits + bit_width),
+        seed,
+    }))
+}
+
+/// C expression for the bit pattern written to a field, or to one of its elements
+///
+/// Integer patterns are restricted to the bits representable by the field, excluding the sign bit,
+/// so that they survive the round trip unaltered. Float patterns are exactly representable.
+fn pattern_expr(tested_field: &TestedField, element_index_expr: Option<&str>) -> String {
+    let TestedField {
+        field,
+        value_repr,
+        seed,
+        ..
+    } = tested_field;
+
+    match value_repr {
+        RepresentableCType::Float { .. } => {
+            let base = format!("{seed}.5");
+            match element_index_expr {
+                Some(index) => format!("({value_repr})({base} + (double)({index}))"),
+                None => format!("({value_repr}){base}"),
+            }
+        }
+        RepresentableCType::Integer { bytes, is_unsigned } => {
+            let bits = match field.kind {
+                FieldKind::BitField { bit_width, .. } => bit_width,
+                _ => u64::from(*bytes) * 8,
+            };
+            let value_bits = bits - u64::from(!is_unsigned);
+            let mask = u64::MAX.checked_shr(64 - value_bits as u32).unwrap_or(0);
+
+            // distinct, irregular bit patterns per field
+            let base = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ 0x0123_4567_89ab_cdef;
+            match element_index_expr {
+                Some(index) => {
+                    format!("({value_repr})(({base:#x}ull + ({index})) & {mask:#x}ull)")
+                }
+                // an all zero pattern would not reveal a missing store
+                None => match base & mask {
+                    0 => format!("({value_repr}){mask:#x}ull"),
+                    value => format!("({value_repr}){value:#x}ull"),
+                },
+            }
+        }
+        _ => unreachable!("only integers and floats are tested"),
+    }
+}
+
+/// C code wrapping `statement` into nested loops over all indices of an array, if it is one
+///
+/// The statement may refer to the indices `i0`, `i1`, ... and to the row-major element index `i`.
+fn for_each_element(tested_field: &TestedField, statement: &str) -> String {
+    let dimensions = &tested_field.dimensions;
+    if dimensions.is_empty() {
+        return statement.to_owned();
+    }
+
+    let mut code = String::new();
+    for (dimension, length) in dimensions.iter().enumerate() {
+        let indentation = "\t".repeat(dimension);
+        code.push_str(&format!(
+            "{indentation}for (uintptr_t i{dimension} = 0; i{dimension} < {length}; i{dimension}++)\n"
+        ));
+    }
+
+    // row-major order, as in C
+    let element_index_expr = dimensions
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold("i0".to_owned(), |expr, (dimension, length)| {
+            format!("({expr}) * {length} + i{dimension}")
+        });
+
+    let indentation = "\t".repeat(dimensions.len());
+    code.push_str(&format!("{indentation}{{\n"));
+    code.push_str(&format!(
+        "{indentation}\tuintptr_t i = {element_index_expr};\n"
+    ));
+    for line in statement.lines() {
+        code.push_str(&format!("{indentation}\t{line}\n"));
+    }
+    code.push_str(&format!("{indentation}}}"));
+    code
+}
+
+/// Arguments indexing one element of an array field, including the leading comma
+fn index_arguments(tested_field: &TestedField) -> String {
+    (0..tested_field.dimensions.len())
+        .map(|dimension| format!(", i{dimension}"))
+        .collect()
+}
+
+/// C code setting a field to its pattern
+fn set_code(tested_field: &TestedField, struct_name: &str, prefix: &str) -> String {
+    let identifier = tested_field.field.identifier();
+    match tested_field.dimensions.is_empty() {
+        true => format!(
+            "{prefix}set__{struct_name}__{identifier}(buf, {});",
+            pattern_expr(tested_field, None)
+        ),
+        false => for_each_element(
+            tested_field,
+            &format!(
+                "{prefix}set_at__{struct_name}__{identifier}(buf{}, {});",
+                index_arguments(tested_field),
+                pattern_expr(tested_field, Some("i"))
+            ),
+        ),
+    }
+}
+
+/// C code checking that a field holds its pattern, reporting `failure` otherwise
+fn check_code(
+    tested_field: &TestedField,
+    struct_name: &str,
+    prefix: &str,
+    failure: &str,
+) -> String {
+    let identifier = tested_field.field.identifier();
+    let field_name = tested_field.field.name();
+    let report = format!(
+        "\tfprintf(stderr, \"{struct_name}.{field_name}: {failure}\\n\");\n\
+        \tfailures++;\n"
+    );
+
+    match tested_field.dimensions.is_empty() {
+        true => format!(
+            "if ({prefix}get__{struct_name}__{identifier}(buf) != {}) {{\n{report}}}",
+            pattern_expr(tested_field, None)
+        ),
+        false => for_each_element(
+            tested_field,
+            &format!(
+                "if ({prefix}get_at__{struct_name}__{identifier}(buf{}) != {}) {{\n{report}}}",
+                index_arguments(tested_field),
+                pattern_expr(tested_field, Some("i"))
+            ),
+        ),
+    }
+}
@@ -0,0 +1,62 @@
+use log::{debug, info};
+
+use super::{CSection, CSnippet, CStaticAssert, FieldKind, RecordLayout};
+
+/// Emit static assertions guarding the size of a given struct or union and the offset of each of
+/// its fields
+///
+/// Compiled for the foreign target, these fail if the layout assumed by the accessor functions
+/// deviates from the actual one, e.g. due to different compiler flags or pragmas.
+pub fn insert_static_asserts(code_snippets: &mut Vec<CSnippet>, struct_: &RecordLayout) {
+    info!("generating static assertions for {:?}", struct_.name);
+
+    let RecordLayout {
+        name,
+        c_type,
+        size_bytes,
+        ..
+    } = struct_;
+
+    code_snippets.push(
+        CSection {
+            title: format!(" {name} "),
+            comment: Default::default(),
+        }
+        .into(),
+    );
+
+    code_snippets.push(
+        CStaticAssert {
+            comment: format!("Size of `{c_type}` in bytes"),
+            condition: format!("sizeof({c_type}) == {size_bytes}"),
+            message: format!("size of {name} differs from the one assumed by c-abi-lens"),
+        }
+        .into(),
+    );
+
+    for field in &struct_.fields {
+        // bit-fields are not addressable, hence have no offset in bytes
+        if let FieldKind::BitField { .. } = field.kind {
+            debug!(
+                "    skipping bit-field {:?}, its offset can not be asserted",
+                field.name()
+            );
+            continue;
+        }
+
+        let field_name = field.name();
+        let offset_bytes = field.offset_bytes();
+        code_snippets.push(
+            CStaticAssert {
+                comment: format!("Offset of `{field_name}` in `{c_type}` in bytes"),
+                condition: format!("offsetof({c_type}, {field_name}) == {offset_bytes}"),
+                message: format!(
+                    "offset of {name}.{field_name} differs from the one assumed by c-abi-lens"
+                ),
+            }
+            .into(),
+        );
+    }
+
+    code_snippets.push(CSnippet::Newline);
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+/// Byte order of a target architecture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// The [`Endianness`] of the host this tool was built for
+    pub fn native() -> Self {
+        match cfg!(target_endian = "big") {
+            true => Self::Big,
+            false => Self::Little,
+        }
+    }
+
+    /// Derive the [`Endianness`] from a (normalized) LLVM target triple
+    ///
+    /// Only the architecture component of the triple is considered. Architectures not known to be
+    /// big-endian are assumed to be little-endian.
+    pub fn of_target_triple(triple: &str) -> Self {
+        let arch = triple.split('-').next().unwrap_or_default();
+
+        // little-endian variants of otherwise big-endian architectures
+        if arch.ends_with("le") || arch.ends_with("el") || arch == "sparcel" {
+            return Self::Little;
+        }
+
+        let big_endian_arch_prefixes = [
+            "aarch64_be",
+            "armeb",
+            "bpfeb",
+            "lanai",
+            "m68k",
+            "mips",
+            "powerpc",
+            "ppc",
+            "s390x",
+            "sparc",
+            "systemz",
+            "tce",
+            "thumbeb",
+        ];
+
+        if big_endian_arch_prefixes
+            .iter()
+            .any(|prefix| arch.starts_with(prefix))
+        {
+            Self::Big
+        } else {
+            Self::Little
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Endianness;
+
+    #[test]
+    fn test_endianness_of_target_triple() {
+        let cases = [
+            ("wasm32-unknown-wasi", Endianness::Little),
+            ("x86_64-unknown-linux-gnu", Endianness::Little),
+            ("aarch64-unknown-none", Endianness::Little),
+            ("aarch64_be-unknown-none", Endianness::Big),
+            ("powerpc-unknown-eabi", Endianness::Big),
+            ("powerpc64le-unknown-linux-gnu", Endianness::Little),
+            ("mips-unknown-linux-gnu", Endianness::Big),
+            ("mipsel-unknown-linux-gnu", Endianness::Little),
+            ("sparcel-unknown-none", Endianness::Little),
+            ("s390x-unknown-linux-gnu", Endianness::Big),
+        ];
+
+        for (triple, expected) in cases {
+            assert_eq!(Endianness::of_target_triple(triple), expected, "{triple}");
+        }
+    }
+}
@@ -3,7 +3,9 @@ use super::RepresentableCType;
 /// Generic representation of a snippet of C-Code
 pub enum CSnippet {
     Include(CInclude),
+    Define(CDefine),
     Func(CFunc),
+    StaticAssert(CStaticAssert),
     Section(CSection),
     Newline,
 }
@@ -14,12 +16,24 @@ impl From<CInclude> for CSnippet {
     }
 }
 
+impl From<CDefine> for CSnippet {
+    fn from(value: CDefine) -> Self {
+        Self::Define(value)
+    }
+}
+
 impl From<CFunc> for CSnippet {
     fn from(value: CFunc) -> Self {
         Self::Func(value)
     }
 }
 
+impl From<CStaticAssert> for CSnippet {
+    fn from(value: CStaticAssert) -> Self {
+        Self::StaticAssert(value)
+    }
+}
+
 impl From<CSection> for CSnippet {
     fn from(value: CSection) -> Self {
         Self::Section(value)
@@ -31,7 +45,6 @@ pub enum CInclude {
     /// Using `<...>`
     System(String),
     /// Using `"..."`
-    #[allow(dead_code)]
     Library(String),
 }
 
@@ -39,11 +52,80 @@ impl CInclude {
     pub fn generate(&self) -> String {
         match self {
             Self::System(h) => format!("#include<{h}>\n"),
-            Self::Library(h) => format!(r#"#include"{h}"\n"#),
+            Self::Library(h) => format!("#include\"{h}\"\n"),
         }
     }
 }
 
+/// Formats a comment string into what C actually considers a comment (e.g. each line prefixed with `// `)
+fn format_comment(comment: &str) -> String {
+    let space = " ";
+    let mut result = String::from("/*");
+    for line in comment.lines() {
+        if !line.is_empty() {
+            result.push_str(space);
+            result.push_str(line);
+        }
+        result.push_str("\n *");
+    }
+    result.push_str("/\n");
+    result
+}
+
+/// Representation of an object-like preprocessor macro in C
+pub struct CDefine {
+    pub comment: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl CDefine {
+    /// Generate macro definition code
+    ///
+    /// # Arguments
+    ///
+    /// - `emit_comment`: whether to also emit a comment explaining the macro up-front
+    pub fn generate(&self, emit_comment: bool) -> String {
+        let Self { name, value, .. } = self;
+
+        let comment = if emit_comment {
+            format_comment(&self.comment)
+        } else {
+            Default::default()
+        };
+
+        format!("{comment}#define {name} {value}\n")
+    }
+}
+
+/// Representation of a static assertion in C (C11)
+pub struct CStaticAssert {
+    pub comment: String,
+    pub condition: String,
+    pub message: String,
+}
+
+impl CStaticAssert {
+    /// Generate static assertion code
+    ///
+    /// # Arguments
+    ///
+    /// - `emit_comment`: whether to also emit a comment explaining the assertion up-front
+    pub fn generate(&self, emit_comment: bool) -> String {
+        let Self {
+            condition, message, ..
+        } = self;
+
+        let comment = if emit_comment {
+            format_comment(&self.comment)
+        } else {
+            Default::default()
+        };
+
+        format!("{comment}_Static_assert({condition}, {message:?});\n")
+    }
+}
+
 /// Representation of a function in C
 pub struct CFunc {
     pub comment: String,
@@ -72,7 +154,7 @@ impl CFunc {
         } = self;
 
         let comment = if emit_comment {
-            self.format_comment()
+            format_comment(&self.comment)
         } else {
             Default::default()
         };
@@ -95,21 +177,6 @@ impl CFunc {
         maybe_prefix.map(|x| format!("{x} ")).unwrap_or_default()
     }
 
-    /// Formats a comment string into what C actually considers a comment (e.g. each line prefixed with `// `)
-    fn format_comment(&self) -> String {
-        let space = " ";
-        let mut result = String::from("/*");
-        for line in self.comment.lines() {
-            if !line.is_empty() {
-                result.push_str(space);
-                result.push_str(line);
-            }
-            result.push_str("\n *");
-        }
-        result.push_str("/\n");
-        result
-    }
-
     /// Formats a function body
     ///
     /// No trailing newline
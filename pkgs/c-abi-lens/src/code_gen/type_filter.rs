@@ -0,0 +1,153 @@
+use color_eyre::{Result, eyre::WrapErr};
+use regex::Regex;
+
+use super::FieldLayout;
+
+/// Selection of the types, and of their fields, to gather the layout of
+///
+/// All regular expressions have to match the entire name, not just a part of it. The default
+/// filter selects everything.
+#[derive(Debug, Clone, Default)]
+pub struct TypeFilter {
+    /// Only types with a name matching any of these, or all types if empty
+    pub include_types: Vec<Regex>,
+
+    /// No types with a name matching any of these
+    pub exclude_types: Vec<Regex>,
+
+    /// No fields with a `type.field` name matching any of these, nor any field nested within them
+    pub exclude_fields: Vec<Regex>,
+
+    /// Only types declared in the main file, or in a header matched by `allowed_headers`
+    pub main_file_only: bool,
+
+    /// Paths of the headers from which types are selected in addition to the main file
+    ///
+    /// Implies `main_file_only` if not empty.
+    pub allowed_headers: Vec<Regex>,
+}
+
+impl TypeFilter {
+    /// Compile the given patterns into a filter
+    pub fn new(
+        include_types: &[String],
+        exclude_types: &[String],
+        exclude_fields: &[String],
+        main_file_only: bool,
+        allowed_headers: &[String],
+    ) -> Result<Self> {
+        Ok(Self {
+            include_types: compile_patterns(include_types)?,
+            exclude_types: compile_patterns(exclude_types)?,
+            exclude_fields: compile_patterns(exclude_fields)?,
+            main_file_only,
+            allowed_headers: compile_patterns(allowed_headers)?,
+        })
+    }
+
+    /// Whether a type declaration is selected, based on where it is declared
+    pub fn selects_declaration(&self, declaration: &clang::Entity) -> bool {
+        if !self.main_file_only && self.allowed_headers.is_empty() {
+            return true;
+        }
+
+        if declaration.is_in_main_file() {
+            return true;
+        }
+
+        let Some(path) = declaration
+            .get_location()
+            .and_then(|l| l.get_file_location().file)
+            .map(|f| f.get_path())
+        else {
+            return false;
+        };
+        let path = path.to_string_lossy();
+        self.allowed_headers.iter().any(|r| r.is_match(&path))
+    }
+
+    /// Whether a type is selected, based on its name
+    pub fn selects_type(&self, type_name: &str) -> bool {
+        (self.include_types.is_empty() || self.include_types.iter().any(|r| r.is_match(type_name)))
+            && !self.exclude_types.iter().any(|r| r.is_match(type_name))
+    }
+
+    /// Whether a field of a selected type is selected
+    ///
+    /// A field is not selected if it or any of the fields it is nested within is excluded.
+    pub fn selects_field(&self, type_name: &str, field: &FieldLayout) -> bool {
+        (1..=field.path.len()).all(|depth| {
+            let name = format!("{type_name}.{}", field.path[..depth].join("."));
+            !self.exclude_fields.iter().any(|r| r.is_match(&name))
+        })
+    }
+}
+
+/// Compile patterns such that they only match entire names
+pub fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(&format!("^(?:{p})$")).wrap_err_with(|| format!("invalid regex {p:?}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypeFilter;
+    use crate::code_gen::{FieldKind, FieldLayout};
+
+    fn filter(include: &[&str], exclude: &[&str], exclude_fields: &[&str]) -> TypeFilter {
+        let strings = |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        TypeFilter::new(
+            &strings(include),
+            &strings(exclude),
+            &strings(exclude_fields),
+            false,
+            &[],
+        )
+        .unwrap()
+    }
+
+    fn field(path: &[&str]) -> FieldLayout {
+        FieldLayout {
+            path: path.iter().map(|s| s.to_string()).collect(),
+            offset_bits: 0,
+            align_bytes: 1,
+            kind: FieldKind::Record {
+                type_name: "struct inner".to_owned(),
+                size_bytes: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_default_selects_everything() {
+        let filter = TypeFilter::default();
+        assert!(filter.selects_type("__pthread_cond_s"));
+        assert!(filter.selects_field("APEX_MESSAGE", &field(&["length"])));
+    }
+
+    #[test]
+    fn test_type_patterns_match_entire_names() {
+        let filter = filter(&["APEX_.*", "PARTITION_STATUS_TYPE"], &[".*_INTERNAL"], &[]);
+        assert!(filter.selects_type("APEX_BYTE"));
+        assert!(filter.selects_type("PARTITION_STATUS_TYPE"));
+        assert!(!filter.selects_type("NOT_APEX_BYTE"));
+        assert!(!filter.selects_type("PARTITION_STATUS_TYPE_2"));
+        assert!(!filter.selects_type("APEX_INTERNAL"));
+    }
+
+    #[test]
+    fn test_excluded_fields_include_nested_ones() {
+        let filter = filter(&[], &[], &["S\\.inner"]);
+        assert!(!filter.selects_field("S", &field(&["inner"])));
+        assert!(!filter.selects_field("S", &field(&["inner", "x"])));
+        assert!(filter.selects_field("S", &field(&["inner_2"])));
+        assert!(filter.selects_field("T", &field(&["inner"])));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(TypeFilter::new(&["(".to_owned()], &[], &[], false, &[]).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use color_eyre::{Result, eyre::bail};
+use log::debug;
+
+use super::{MacroConstant, RepresentableCType};
+
+/// Value types of WebAssembly globals, as far as they can hold a C integer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WasmValueType {
+    I32,
+    I64,
+}
+
+impl WasmValueType {
+    /// The value type holding all values of a C integer type
+    fn for_repr(repr: &RepresentableCType) -> Result<Self> {
+        match repr {
+            RepresentableCType::Integer { bytes: 1..=4, .. } => Ok(Self::I32),
+            RepresentableCType::Integer { bytes: 8, .. } => Ok(Self::I64),
+            _ => bail!("no WebAssembly value type can hold a {repr}"),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+        }
+    }
+}
+
+/// A global exported from the generated module
+struct WasmGlobal<'a> {
+    name: &'a str,
+    value_type: WasmValueType,
+
+    /// The value as it is written in the text format, i.e. with the macro's signedness
+    literal: String,
+
+    /// The value as the two's complement bit pattern of the value type
+    bits: i64,
+}
+
+impl<'a> WasmGlobal<'a> {
+    fn new(constant: &'a MacroConstant) -> Result<Self> {
+        let value_type = WasmValueType::for_repr(&constant.repr)?;
+        let is_unsigned = matches!(
+            constant.repr,
+            RepresentableCType::Integer {
+                is_unsigned: true,
+                ..
+            }
+        );
+
+        let literal = match is_unsigned {
+            true => constant.unsigned_value.to_string(),
+            false => constant.signed_value.to_string(),
+        };
+        let bits = match value_type {
+            WasmValueType::I32 => i64::from(constant.unsigned_value as u32 as i32),
+            WasmValueType::I64 => constant.signed_value,
+        };
+
+        Ok(Self {
+            name: &constant.name,
+            value_type,
+            literal,
+            bits,
+        })
+    }
+}
+
+/// Gather one global per constant, in the order of the constants
+fn wasm_globals(constants: &[MacroConstant]) -> Result<Vec<WasmGlobal<'_>>> {
+    constants
+        .iter()
+        .inspect(|c| debug!("exporting macro {:?} as global", c.name))
+        .map(WasmGlobal::new)
+        .collect()
+}
+
+/// Generate a WebAssembly text format (WAT) module exporting each constant as an immutable global
+///
+/// Each global is named and exported by the name of its macro, and is an `i64` if the macro's
+/// type is 8 bytes wide, an `i32` otherwise. The globals are in the order of the `constants`, so
+/// the same input always yields the same module. The `header` is prepended as a line comment.
+pub fn generate_wat_module(constants: &[MacroConstant], header: &str) -> Result<String> {
+    let globals = wasm_globals(constants)?;
+
+    let mut wat = String::new();
+    for line in header.lines() {
+        wat.push_str(format!(";; {line}").trim_end());
+        wat.push('\n');
+    }
+    wat.push_str("(module\n");
+    for WasmGlobal {
+        name,
+        value_type,
+        literal,
+        ..
+    } in &globals
+    {
+        let value_type = value_type.name();
+        wat.push_str(&format!(
+            "  (global ${name} (export \"{name}\") {value_type} ({value_type}.const {literal}))\n"
+        ));
+    }
+    wat.push_str(")\n");
+
+    Ok(wat)
+}
+
+/// Generate a binary WebAssembly module exporting each constant as an immutable global
+///
+/// The module is the binary encoding of the one generated by [`generate_wat_module`], without the
+/// header comment.
+pub fn generate_wasm_module(constants: &[MacroConstant]) -> Result<Vec<u8>> {
+    /// Id of the global section
+    const GLOBAL_SECTION_ID: u8 = 6;
+
+    /// Id of the export section
+    const EXPORT_SECTION_ID: u8 = 7;
+
+    let globals = wasm_globals(constants)?;
+
+    let mut global_section = Vec::new();
+    write_unsigned_leb128(&mut global_section, globals.len() as u64);
+    for WasmGlobal {
+        value_type, bits, ..
+    } in &globals
+    {
+        let (value_type, const_opcode) = match value_type {
+            WasmValueType::I32 => (0x7f, 0x41),
+            WasmValueType::I64 => (0x7e, 0x42),
+        };
+        // immutable, initialized by a constant expression
+        global_section.extend([value_type, 0x00, const_opcode]);
+        write_signed_leb128(&mut global_section, *bits);
+        global_section.push(0x0b);
+    }
+
+    let mut export_section = Vec::new();
+    write_unsigned_leb128(&mut export_section, globals.len() as u64);
+    for (index, WasmGlobal { name, .. }) in globals.iter().enumerate() {
+        write_unsigned_leb128(&mut export_section, name.len() as u64);
+        export_section.extend(name.as_bytes());
+        // export of a global
+        export_section.push(0x03);
+        write_unsigned_leb128(&mut export_section, index as u64);
+    }
+
+    // magic number and version
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    for (id, section) in [
+        (GLOBAL_SECTION_ID, global_section),
+        (EXPORT_SECTION_ID, export_section),
+    ] {
+        wasm.push(id);
+        write_unsigned_leb128(&mut wasm, section.len() as u64);
+        wasm.extend(section);
+    }
+
+    Ok(wasm)
+}
+
+/// Append `value` in the unsigned LEB128 encoding
+fn write_unsigned_leb128(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        match value {
+            0 => {
+                bytes.push(byte);
+                return;
+            }
+            _ => bytes.push(byte | 0x80),
+        }
+    }
+}
+
+/// Append `value` in the signed LEB128 encoding
+fn write_signed_leb128(bytes: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        // arithmetic shift, keeps the sign
+        value >>= 7;
+        let sign_bit_clear = byte & 0x40 == 0;
+        if (value == 0 && sign_bit_clear) || (value == -1 && !sign_bit_clear) {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        generate_wasm_module, generate_wat_module, write_signed_leb128, write_unsigned_leb128,
+    };
+    use crate::code_gen::{MacroConstant, RepresentableCType};
+
+    fn constant(name: &str, bytes: u8, is_unsigned: bool, value: i64) -> MacroConstant {
+        MacroConstant {
+            name: name.to_owned(),
+            repr: RepresentableCType::Integer { bytes, is_unsigned },
+            signed_value: value,
+            unsigned_value: value as u64,
+        }
+    }
+
+    #[test]
+    fn test_leb128() {
+        let signed = |v| {
+            let mut bytes = Vec::new();
+            write_signed_leb128(&mut bytes, v);
+            bytes
+        };
+        let unsigned = |v| {
+            let mut bytes = Vec::new();
+            write_unsigned_leb128(&mut bytes, v);
+            bytes
+        };
+
+        assert_eq!(unsigned(0), [0x00]);
+        assert_eq!(unsigned(624_485), [0xe5, 0x8e, 0x26]);
+        assert_eq!(signed(0), [0x00]);
+        assert_eq!(signed(63), [0x3f]);
+        assert_eq!(signed(64), [0xc0, 0x00]);
+        assert_eq!(signed(-1), [0x7f]);
+        assert_eq!(signed(-123_456), [0xc0, 0xbb, 0x78]);
+        assert_eq!(signed(i64::MIN).len(), 10);
+    }
+
+    #[test]
+    fn test_wat_module() {
+        let constants = [
+            constant("SYSTEM_LIMIT_NUMBER_OF_PARTITIONS", 4, false, 32),
+            constant("SYSTEM_LIMIT_MASK", 4, true, 0xffff_ffff),
+            constant("SYSTEM_LIMIT_OFFSET", 8, false, -1),
+        ];
+
+        let wat = generate_wat_module(&constants, "generated\n\nby test").unwrap();
+        assert_eq!(
+            wat,
+            "\
+            ;; generated\n\
+            ;;\n\
+            ;; by test\n\
+            (module\n\
+            \x20 (global $SYSTEM_LIMIT_NUMBER_OF_PARTITIONS (export \"SYSTEM_LIMIT_NUMBER_OF_PARTITIONS\") i32 (i32.const 32))\n\
+            \x20 (global $SYSTEM_LIMIT_MASK (export \"SYSTEM_LIMIT_MASK\") i32 (i32.const 4294967295))\n\
+            \x20 (global $SYSTEM_LIMIT_OFFSET (export \"SYSTEM_LIMIT_OFFSET\") i64 (i64.const -1))\n\
+            )\n\
+            "
+        );
+    }
+
+    #[test]
+    fn test_wasm_module() {
+        let constants = [
+            constant("A", 4, true, 0xffff_ffff),
+            constant("B", 8, false, 2),
+        ];
+
+        let wasm = generate_wasm_module(&constants).unwrap();
+        #[rustfmt::skip]
+        assert_eq!(
+            wasm,
+            [
+                0x00, b'a', b's', b'm', 0x01, 0x00, 0x00, 0x00,
+                // global section
+                0x06, 0x0b, 0x02,
+                0x7f, 0x00, 0x41, 0x7f, 0x0b,
+                0x7e, 0x00, 0x42, 0x02, 0x0b,
+                // export section
+                0x07, 0x09, 0x02,
+                0x01, b'A', 0x03, 0x00,
+                0x01, b'B', 0x03, 0x01,
+            ]
+        );
+    }
+}
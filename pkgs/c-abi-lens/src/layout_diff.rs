@@ -0,0 +1,584 @@
+//! Comparison of the layout of the same types on two different targets
+
+use std::fmt::Write;
+
+use color_eyre::Result;
+use serde::Serialize;
+
+use crate::code_gen::{
+    EnumLayout, FieldKind, FieldLayout, LensLayout, RecordLayout, RepresentableCType, TargetLayout,
+    TypeLayout,
+};
+
+/// The differences between the layouts of all types on two targets
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutDiff {
+    pub left: TargetLayout,
+    pub right: TargetLayout,
+
+    /// Whether all types present on both targets can be copied verbatim between them
+    pub compatible: bool,
+    pub types: Vec<TypeDiff>,
+}
+
+/// The differences of one type between two targets
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeDiff {
+    pub name: String,
+
+    /// An instance of this type can be copied verbatim between the two targets
+    pub compatible: bool,
+    pub differences: Vec<Difference>,
+}
+
+/// One aspect in which a type or one of its fields differs between two targets
+#[derive(Debug, Clone, Serialize)]
+pub struct Difference {
+    /// Path of the field which differs, empty if the type itself differs
+    pub path: Vec<String>,
+    pub aspect: Aspect,
+    pub left: String,
+    pub right: String,
+}
+
+/// The aspects in which a type or one of its fields may differ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aspect {
+    /// Only present on one of the targets
+    Presence,
+    Kind,
+    Size,
+    Offset,
+    Alignment,
+    Signedness,
+    Endianness,
+    BitWidth,
+    Dimensions,
+    EnumeratorValue,
+}
+
+impl Aspect {
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Presence => "presence",
+            Self::Kind => "kind",
+            Self::Size => "size in bytes",
+            Self::Offset => "offset in bits",
+            Self::Alignment => "alignment in bytes",
+            Self::Signedness => "signedness",
+            Self::Endianness => "byte order",
+            Self::BitWidth => "bit width",
+            Self::Dimensions => "dimensions",
+            Self::EnumeratorValue => "value",
+        }
+    }
+}
+
+impl LayoutDiff {
+    /// Compare the layouts of all types in `left` with the types of the same name in `right`
+    pub fn new(left: &LensLayout, right: &LensLayout) -> Result<Self> {
+        let mut types = Vec::new();
+
+        for left_type in &left.types {
+            let right_type = right
+                .types
+                .iter()
+                .find(|t| type_name(t) == type_name(left_type));
+
+            let mut differences = Vec::new();
+            match (left_type, right_type) {
+                (_, None) => differences.push(Difference::new(&[], Aspect::Presence, "yes", "no")),
+                (TypeLayout::Record(l), Some(TypeLayout::Record(r))) => {
+                    diff_records(&mut differences, l, r, left, right)?
+                }
+                (TypeLayout::Enum(l), Some(TypeLayout::Enum(r))) => {
+                    diff_enums(&mut differences, l, r, left, right)
+                }
+                (_, Some(right_type)) => differences.push(Difference::new(
+                    &[],
+                    Aspect::Kind,
+                    type_kind(left_type),
+                    type_kind(right_type),
+                )),
+            }
+
+            types.push(TypeDiff::new(type_name(left_type), differences));
+        }
+
+        // types only present on the right target
+        for right_type in &right.types {
+            if !left
+                .types
+                .iter()
+                .any(|t| type_name(t) == type_name(right_type))
+            {
+                types.push(TypeDiff::new(
+                    type_name(right_type),
+                    vec![Difference::new(&[], Aspect::Presence, "no", "yes")],
+                ));
+            }
+        }
+
+        Ok(Self {
+            left: left.target.clone(),
+            right: right.target.clone(),
+            compatible: types.iter().all(|t| t.compatible),
+            types,
+        })
+    }
+
+    /// Render a human readable report
+    pub fn to_report(&self) -> String {
+        let mut report = format!(
+            "comparing {} (left) with {} (right)\n\n",
+            self.left.triple, self.right.triple
+        );
+
+        for type_diff in &self.types {
+            if type_diff.compatible {
+                let _ = writeln!(
+                    report,
+                    "{}: compatible, can be copied verbatim",
+                    type_diff.name
+                );
+                continue;
+            }
+
+            let _ = writeln!(
+                report,
+                "{}: INCOMPATIBLE, requires the lens",
+                type_diff.name
+            );
+            for Difference {
+                path,
+                aspect,
+                left,
+                right,
+            } in &type_diff.differences
+            {
+                let subject = match path.is_empty() {
+                    true => aspect.description().to_owned(),
+                    false => format!("{}: {}", path.join("."), aspect.description()),
+                };
+                let _ = writeln!(report, "    {subject:.<48} {left} vs {right}");
+            }
+        }
+
+        let _ = write!(
+            report,
+            "\n{} of {} types are compatible\n",
+            self.types.iter().filter(|t| t.compatible).count(),
+            self.types.len()
+        );
+
+        report
+    }
+}
+
+impl TypeDiff {
+    fn new(name: &str, differences: Vec<Difference>) -> Self {
+        Self {
+            name: name.to_owned(),
+            compatible: differences.is_empty(),
+            differences,
+        }
+    }
+}
+
+impl Difference {
+    fn new(path: &[String], aspect: Aspect, left: impl ToString, right: impl ToString) -> Self {
+        Self {
+            path: path.to_vec(),
+            aspect,
+            left: left.to_string(),
+            right: right.to_string(),
+        }
+    }
+}
+
+fn type_name(type_layout: &TypeLayout) -> &str {
+    match type_layout {
+        TypeLayout::Record(r) => &r.name,
+        TypeLayout::Enum(e) => &e.name,
+    }
+}
+
+fn type_kind(type_layout: &TypeLayout) -> &'static str {
+    match type_layout {
+        TypeLayout::Record(_) => "record",
+        TypeLayout::Enum(_) => "enum",
+    }
+}
+
+/// Push a [`Difference`] if `left` and `right` differ
+fn push_if_different<T: PartialEq + ToString>(
+    differences: &mut Vec<Difference>,
+    path: &[String],
+    aspect: Aspect,
+    left: T,
+    right: T,
+) {
+    if left != right {
+        differences.push(Difference::new(path, aspect, left, right));
+    }
+}
+
+fn diff_records(
+    differences: &mut Vec<Difference>,
+    left: &RecordLayout,
+    right: &RecordLayout,
+    left_layout: &LensLayout,
+    right_layout: &LensLayout,
+) -> Result<()> {
+    push_if_different(
+        differences,
+        &[],
+        Aspect::Size,
+        left.size_bytes,
+        right.size_bytes,
+    );
+    push_if_different(
+        differences,
+        &[],
+        Aspect::Alignment,
+        left.align_bytes,
+        right.align_bytes,
+    );
+
+    for left_field in &left.fields {
+        match right.fields.iter().find(|f| f.path == left_field.path) {
+            Some(right_field) => diff_fields(
+                differences,
+                left_field,
+                right_field,
+                left_layout,
+                right_layout,
+            )?,
+            None => differences.push(Difference::new(
+                &left_field.path,
+                Aspect::Presence,
+                "yes",
+                "no",
+            )),
+        }
+    }
+
+    for right_field in &right.fields {
+        if !left.fields.iter().any(|f| f.path == right_field.path) {
+            differences.push(Difference::new(
+                &right_field.path,
+                Aspect::Presence,
+                "no",
+                "yes",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn diff_fields(
+    differences: &mut Vec<Difference>,
+    left: &FieldLayout,
+    right: &FieldLayout,
+    left_layout: &LensLayout,
+    right_layout: &LensLayout,
+) -> Result<()> {
+    let path = &left.path;
+
+    push_if_different(
+        differences,
+        path,
+        Aspect::Kind,
+        field_kind_name(&left.kind),
+        field_kind_name(&right.kind),
+    );
+    push_if_different(
+        differences,
+        path,
+        Aspect::Offset,
+        left.offset_bits,
+        right.offset_bits,
+    );
+    push_if_different(
+        differences,
+        path,
+        Aspect::Size,
+        left.size_bytes()?,
+        right.size_bytes()?,
+    );
+    push_if_different(
+        differences,
+        path,
+        Aspect::Alignment,
+        left.align_bytes,
+        right.align_bytes,
+    );
+
+    if let (
+        FieldKind::BitField {
+            bit_width: left_width,
+            ..
+        },
+        FieldKind::BitField {
+            bit_width: right_width,
+            ..
+        },
+    ) = (&left.kind, &right.kind)
+    {
+        push_if_different(differences, path, Aspect::BitWidth, left_width, right_width);
+    }
+
+    if let (Some(left_dimensions), Some(right_dimensions)) =
+        (field_dimensions(&left.kind), field_dimensions(&right.kind))
+    {
+        push_if_different(
+            differences,
+            path,
+            Aspect::Dimensions,
+            format!("{left_dimensions:?}"),
+            format!("{right_dimensions:?}"),
+        );
+    }
+
+    let (left_element, right_element) = (field_element_repr(left), field_element_repr(right));
+    if let (
+        Some(RepresentableCType::Integer {
+            is_unsigned: left_unsigned,
+            ..
+        }),
+        Some(RepresentableCType::Integer {
+            is_unsigned: right_unsigned,
+            ..
+        }),
+    ) = (&left_element, &right_element)
+    {
+        push_if_different(
+            differences,
+            path,
+            Aspect::Signedness,
+            signedness(*left_unsigned),
+            signedness(*right_unsigned),
+        );
+    }
+
+    // the byte order only matters for values spanning multiple bytes
+    let spans_multiple_bytes =
+        |element: &Option<RepresentableCType>, field: &FieldLayout| match (element, &field.kind) {
+            (_, FieldKind::BitField { .. }) => field.size_bytes().is_ok_and(|s| s > 1),
+            (Some(element), _) => element.element_size_bytes().is_ok_and(|s| s > 1),
+            (None, _) => false,
+        };
+    if spans_multiple_bytes(&left_element, left) || spans_multiple_bytes(&right_element, right) {
+        push_if_different(
+            differences,
+            path,
+            Aspect::Endianness,
+            format!("{:?}", left_layout.target.endianness),
+            format!("{:?}", right_layout.target.endianness),
+        );
+    }
+
+    Ok(())
+}
+
+fn diff_enums(
+    differences: &mut Vec<Difference>,
+    left: &EnumLayout,
+    right: &EnumLayout,
+    left_layout: &LensLayout,
+    right_layout: &LensLayout,
+) {
+    if let (
+        RepresentableCType::Integer {
+            bytes: left_bytes,
+            is_unsigned: left_unsigned,
+        },
+        RepresentableCType::Integer {
+            bytes: right_bytes,
+            is_unsigned: right_unsigned,
+        },
+    ) = (&left.repr, &right.repr)
+    {
+        push_if_different(differences, &[], Aspect::Size, left_bytes, right_bytes);
+        push_if_different(
+            differences,
+            &[],
+            Aspect::Signedness,
+            signedness(*left_unsigned),
+            signedness(*right_unsigned),
+        );
+        if *left_bytes > 1 || *right_bytes > 1 {
+            push_if_different(
+                differences,
+                &[],
+                Aspect::Endianness,
+                format!("{:?}", left_layout.target.endianness),
+                format!("{:?}", right_layout.target.endianness),
+            );
+        }
+    }
+
+    for left_enumerator in &left.enumerators {
+        let path = [left_enumerator.name.clone()];
+        match right
+            .enumerators
+            .iter()
+            .find(|e| e.name == left_enumerator.name)
+        {
+            Some(right_enumerator) => push_if_different(
+                differences,
+                &path,
+                Aspect::EnumeratorValue,
+                left_enumerator.signed_value,
+                right_enumerator.signed_value,
+            ),
+            None => differences.push(Difference::new(&path, Aspect::Presence, "yes", "no")),
+        }
+    }
+
+    for right_enumerator in &right.enumerators {
+        if !left
+            .enumerators
+            .iter()
+            .any(|e| e.name == right_enumerator.name)
+        {
+            let path = [right_enumerator.name.clone()];
+            differences.push(Difference::new(&path, Aspect::Presence, "no", "yes"));
+        }
+    }
+}
+
+pub fn signedness(is_unsigned: bool) -> &'static str {
+    match is_unsigned {
+        true => "unsigned",
+        false => "signed",
+    }
+}
+
+/// Name of the kind of a field, as used in the JSON layout schema
+pub fn field_kind_name(kind: &FieldKind) -> &'static str {
+    match kind {
+        FieldKind::Scalar { .. } => "scalar",
+        FieldKind::BitField { .. } => "bit_field",
+        FieldKind::Array { .. } => "array",
+        FieldKind::RecordArray { .. } => "record_array",
+        FieldKind::Record { .. } => "record",
+        FieldKind::Opaque { .. } => "opaque",
+    }
+}
+
+/// The dimensions of an array field, or `None` if it is no array
+pub fn field_dimensions(kind: &FieldKind) -> Option<Vec<u64>> {
+    match kind {
+        FieldKind::Array { repr } => Some(repr.dimensions()),
+        FieldKind::RecordArray { dimensions, .. } => Some(dimensions.clone()),
+        _ => None,
+    }
+}
+
+/// The representation of a field's value, or of its elements if it is an array
+pub fn field_element_repr(field: &FieldLayout) -> Option<RepresentableCType> {
+    match &field.kind {
+        FieldKind::Scalar { repr, .. }
+        | FieldKind::BitField { repr, .. }
+        | FieldKind::Array { repr } => Some(repr.element_type()),
+        FieldKind::RecordArray { .. } | FieldKind::Record { .. } | FieldKind::Opaque { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Aspect, LayoutDiff};
+    use crate::code_gen::{
+        Endianness, FieldKind, FieldLayout, LensLayout, RecordKind, RecordLayout,
+        RepresentableCType, ScalarMeaning, TargetLayout, TypeLayout,
+    };
+
+    /// A struct with a `long` and a `char`, as laid out by a target
+    fn layout(endianness: Endianness, long_bytes: u8) -> LensLayout {
+        let long_bits = u64::from(long_bytes) * 8;
+        let field = |name: &str, offset_bits, bytes: u8| FieldLayout {
+            path: vec![name.to_owned()],
+            offset_bits,
+            align_bytes: bytes.into(),
+            kind: FieldKind::Scalar {
+                repr: RepresentableCType::Integer {
+                    bytes,
+                    is_unsigned: false,
+                },
+                meaning: ScalarMeaning::Value,
+            },
+        };
+
+        LensLayout {
+            target: TargetLayout {
+                triple: "test".to_owned(),
+                endianness,
+                pointer_width_bytes: long_bytes,
+            },
+            types: vec![
+                TypeLayout::Record(RecordLayout {
+                    name: "Chars".to_owned(),
+                    c_type: "Chars".to_owned(),
+                    kind: RecordKind::Struct,
+                    size_bytes: 2,
+                    align_bytes: 1,
+                    fields: vec![field("a", 0, 1), field("b", 8, 1)],
+                }),
+                TypeLayout::Record(RecordLayout {
+                    name: "Mixed".to_owned(),
+                    c_type: "Mixed".to_owned(),
+                    kind: RecordKind::Struct,
+                    size_bytes: u64::from(long_bytes) * 2,
+                    align_bytes: long_bytes.into(),
+                    fields: vec![field("l", 0, long_bytes), field("c", long_bits, 1)],
+                }),
+            ],
+            constants: vec![],
+            anonymous_enums: vec![],
+        }
+    }
+
+    #[test]
+    fn test_identical_layouts_are_compatible() {
+        let layout = layout(Endianness::Little, 4);
+        let diff = LayoutDiff::new(&layout, &layout).unwrap();
+        assert!(diff.compatible);
+    }
+
+    #[test]
+    fn test_differing_layouts() {
+        let diff = LayoutDiff::new(
+            &layout(Endianness::Little, 4),
+            &layout(Endianness::Little, 8),
+        )
+        .unwrap();
+        assert!(!diff.compatible);
+        assert!(diff.types[0].compatible);
+
+        let aspects: Vec<_> = diff.types[1]
+            .differences
+            .iter()
+            .map(|d| (d.path.join("."), d.aspect))
+            .collect();
+        assert!(aspects.contains(&(String::new(), Aspect::Size)));
+        assert!(aspects.contains(&("l".to_owned(), Aspect::Size)));
+        assert!(aspects.contains(&("c".to_owned(), Aspect::Offset)));
+    }
+
+    #[test]
+    fn test_endianness_only_matters_for_multi_byte_values() {
+        let diff =
+            LayoutDiff::new(&layout(Endianness::Little, 4), &layout(Endianness::Big, 4)).unwrap();
+        assert!(diff.types[0].compatible);
+
+        let aspects: Vec<_> = diff.types[1]
+            .differences
+            .iter()
+            .map(|d| (d.path.join("."), d.aspect))
+            .collect();
+        assert_eq!(aspects, [("l".to_owned(), Aspect::Endianness)]);
+    }
+}
@@ -1,17 +1,34 @@
-use log::{debug, trace};
+//! Generate accessors for C data types laid out in a foreign ABI, see the `c-abi-lens` binary
+//!
+//! The [`Builder`] configures the code generation for one input file, which is then parsed with a
+//! [`ClangSession`]. The resulting [`Lens`] holds the foreign layout, from which any output can be
+//! generated. This allows build scripts to generate accessors without spawning the binary:
+//!
+//! ```no_run
+//! use c_abi_lens::{Builder, ClangSession, OutputFormat};
+//!
+//! let session = ClangSession::new()?;
+//! let lens = Builder::new("include/ARINC653-wasm.h")
+//!     .clang_arg("--target=wasm32-unknown-none")
+//!     .output_format(OutputFormat::Json)
+//!     .lens(&session)?;
+//! println!("{}", lens.generate()?);
+//! # Ok::<(), color_eyre::Report>(())
+//! ```
+//!
+//! To generate the Rust accessors at compile time instead, use `c_abi_lens_macros::lens!` from the
+//! `c-abi-lens-macros` crate. It is not re-exported here, as that crate depends on this one.
 
-/// Get default clang args from environment variables
-pub fn clang_args_from_env() -> Vec<String> {
-    debug!("adding additional clang_args from environment variables");
-    ["EXTRA_CLANG_ARGS", "BINDGEN_EXTRA_CLANG_ARGS"]
-        .into_iter()
-        .inspect(|s| trace!("reading {s} environment variable"))
-        .map(std::env::var)
-        .filter_map(Result::ok)
-        .flat_map(|s| {
-            s.split_whitespace()
-                .map(String::from)
-                .collect::<Vec<String>>()
-        })
-        .collect()
-}
+#![deny(unsafe_code)]
+
+pub mod abi_evolution;
+mod builder;
+mod clang_args;
+pub mod code_gen;
+pub mod layout_diff;
+pub mod report;
+mod session;
+
+pub use builder::*;
+pub use clang_args::*;
+pub use session::*;
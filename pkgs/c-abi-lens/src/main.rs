@@ -2,32 +2,49 @@
 #![deny(unsafe_code)]
 #![deny(missing_docs)]
 
-use std::{fs::File, io::Write};
-
-use clang::*;
+use std::{fs::File, io::Write, path::PathBuf};
 
+use c_abi_lens::{
+    Builder, ClangSession, CompilationDatabase, OutputFormat, abi_evolution::EvolutionReport,
+    check_file, layout_diff::LayoutDiff,
+};
 use clap::Parser;
 use cli::Cli;
-use code_gen::{CInclude, CSection, CSnippet, insert_struct_functions};
-use color_eyre::{Result, eyre::eyre};
-use log::{debug, error};
+use color_eyre::{Result, eyre::bail};
+use log::{debug, error, info};
 
 mod cli;
-mod code_gen;
-
-const TOOL_NAME: &str = env!("CARGO_BIN_NAME");
-const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 fn main() -> Result<()> {
     // parse the CLI arguments
     let Cli {
         endianness_swap,
+        host_target,
         input_file,
         output_file,
         prefix,
         function_decl_prefix,
         comment,
         only_prototype,
+        deref_helpers,
+        include_type,
+        exclude_type,
+        exclude_field,
+        main_file_only,
+        allow_header,
+        macros,
+        static_asserts,
+        self_test,
+        output_format,
+        diff_target,
+        evolved_from,
+        compile_commands,
+        compile_source,
+        depfile,
+        check,
+        relative_to,
+        strict,
+        report: report_file,
         clang_args: clang_args_suffix,
     } = Cli::parse();
 
@@ -35,148 +52,192 @@ fn main() -> Result<()> {
     colog::init();
     color_eyre::install()?;
 
+    // the exact args the foreign build used, if known
+    let compile_command_args = match compile_commands {
+        Some(compile_commands) => CompilationDatabase::from_directory(compile_commands)?
+            .clang_args_for(compile_source.as_ref().unwrap_or(&input_file))?,
+        None => Vec::new(),
+    };
+
     // read additional clang args, prepend the CLI args
-    let mut clang_args = c_abi_lens::clang_args_from_env();
-    clang_args.extend(clang_args_suffix);
-
-    debug!("initializing clang");
-    let clang = Clang::new().map_err(|e| eyre!("error initializing clang:\n{e}"))?;
-
-    // Create a new `Index`
-    let index = Index::new(&clang, false, true);
-
-    // Parse a source file into a translation unit
-    let mut parser = index.parser(&input_file);
-
-    // Pass all clang args
-    debug!("clang args: {clang_args:#?}");
-    parser.arguments(&clang_args);
-
-    // parse the code into a translation unit
-    debug!("parsing {:?}", input_file);
-    let tu = parser.parse()?;
-
-    // get target information
-    let target = tu.get_target();
-    debug!("target: {target:?}");
-    let Target {
-        triple: target_triple,
-        pointer_width: _target_pointer_width,
-    } = target;
-
-    // Get the structs in this translation unit
-    let structs = tu
-        .get_entity()
-        .get_children()
+    let mut builder = Builder::new(input_file)
+        .clang_args(c_abi_lens::clang_args_from_env()?)
+        .clang_args(compile_command_args)
+        .clang_args(clang_args_suffix)
+        .prefix(prefix)
+        .comment(comment)
+        .only_prototype(only_prototype)
+        .deref_helpers(deref_helpers)
+        .main_file_only(main_file_only)
+        .output_format(output_format)
+        .endianness_swap(endianness_swap);
+    if let Some(function_decl_prefix) = function_decl_prefix {
+        builder = builder.function_decl_prefix(function_decl_prefix);
+    }
+    if let Some(host_target) = host_target {
+        builder = builder.host_target(host_target);
+    }
+    if let Some(relative_to) = relative_to {
+        builder = builder.relative_to(relative_to);
+    }
+    builder = include_type
+        .into_iter()
+        .fold(builder, Builder::include_type);
+    builder = exclude_type
+        .into_iter()
+        .fold(builder, Builder::exclude_type);
+    builder = exclude_field
+        .into_iter()
+        .fold(builder, Builder::exclude_field);
+    builder = allow_header
         .into_iter()
-        .filter(|e| e.get_kind() == EntityKind::StructDecl)
-        .collect::<Vec<_>>();
-
-    let mut code_snippets = Vec::new();
-
-    // section header for the entire library
-    code_snippets.push(
-        CSection {
-            title: " c-abi-lens ".to_owned(),
-            comment: format!(
-                "\
-                THIS CODE IS AUTOGENERATED, DON'T MODIFY BY HAND\n\
-                \n\
-                This header-only library provides access to data type instances represented in\n\
-                a foreign architecture's ABI. In particular, it provides getter and setter\n\
-                functions for struct fields which observe the foreign architecture's memory\n\
-                layout.\n\
-                \n\
-                input_file ........... {input_file:?}\n\
-                clang_args ........... {clang_args:?}\n\
-                target_arch .......... {target_triple}\n\
-                prefix ............... {prefix}\n\
-                function_decl_prefix . {function_decl_prefix:?}\n\
-                emit_comment ......... {comment}\n\
-                only_prototype........ {only_prototype}\n\
-                generated_by ......... {TOOL_NAME} v{TOOL_VERSION}\
-                "
-            ),
+        .fold(builder, Builder::allow_header);
+    builder = macros.into_iter().fold(builder, Builder::macro_pattern);
+
+    let session = ClangSession::new()?;
+
+    // gather the layout of all types
+    let lens = builder.lens(&session)?;
+
+    // compare against a second target instead of generating code
+    if let Some(diff_target) = diff_target {
+        let diff_lens = builder
+            .clone()
+            .clang_arg(format!("--target={diff_target}"))
+            .lens(&session)?;
+
+        let diff = LayoutDiff::new(lens.layout(), diff_lens.layout())?;
+        let report = match output_format {
+            OutputFormat::Json => {
+                let mut json = serde_json::to_string_pretty(&diff)?;
+                json.push('\n');
+                json
+            }
+            _ => diff.to_report(),
+        };
+        write_output(output_file, &report)?;
+
+        if !diff.compatible {
+            std::process::exit(1);
         }
-        .into(),
-    );
+        return Ok(());
+    }
 
-    code_snippets.push(CSnippet::Newline);
+    // classify the changes from an older revision instead of generating code
+    if let Some(evolved_from) = evolved_from {
+        let old_lens = builder.clone().input_file(evolved_from).lens(&session)?;
 
-    // add include of `stdint.h` and `string.h`
-    code_snippets.push(CInclude::System("stdint.h".to_owned()).into());
-    code_snippets.push(CInclude::System("string.h".to_owned()).into());
+        let evolution = EvolutionReport::new(old_lens.layout(), lens.layout())?;
+        let report = match output_format {
+            OutputFormat::Json => {
+                let mut json = serde_json::to_string_pretty(&evolution)?;
+                json.push('\n');
+                json
+            }
+            _ => evolution.to_report(),
+        };
+        write_output(output_file, &report)?;
 
-    // if endianness swap is desired, include the header file for it
-    if endianness_swap {
-        code_snippets.push(CInclude::System("byteswap.h".to_owned()).into());
+        if !evolution.compatible {
+            std::process::exit(1);
+        }
+        return Ok(());
     }
 
-    code_snippets.push(CSnippet::Newline);
-    code_snippets.push(CSnippet::Newline);
-
-    // Print information about the structs
-    for struct_ in structs {
-        if let Err(e) = insert_struct_functions(&mut code_snippets, &struct_, endianness_swap) {
-            error!(
-                "skipping to the next struct, because the following error occured while generating struct functions:\n{e}"
-            )
+    // the output and what it covers, before anything is written
+    debug!("generating code");
+    let (code, self_test_code, report) = match self_test {
+        Some(_) => {
+            let (code, self_test_code, report) = lens.generate_with_self_test_and_report()?;
+            (code, Some(self_test_code), report)
         }
+        None => {
+            let (code, report) = lens.generate_with_report()?;
+            (code, None, report)
+        }
+    };
+    let report_json = report_file
+        .as_ref()
+        .map(|_| -> Result<_> {
+            let mut json = serde_json::to_string_pretty(&report)?;
+            json.push('\n');
+            Ok(json.into_bytes())
+        })
+        .transpose()?;
+    if strict && !report.complete {
+        report.log_skipped();
+        if let (Some(report_file), Some(report_json), false) = (&report_file, &report_json, check) {
+            File::create(report_file)?.write_all(report_json)?;
+        }
+        bail!(
+            "{} items were skipped, refusing to generate an incomplete lens in strict mode",
+            report.skipped.len()
+        );
     }
 
-    code_snippets.push(CSnippet::Newline);
-
-    // apply prefix to all function names, if its not an empty string
-    if !prefix.is_empty() {
-        debug!("applying function prefixes");
-        for snippet in &mut code_snippets {
-            if let CSnippet::Func(code_gen::CFunc { name, .. }) = snippet {
-                *name = format!("{prefix}_{name}")
-            }
-        }
+    // all files to write, the output file last as that's what a build system likely depends on
+    let mut outputs = Vec::new();
+    if let (Some(report_file), Some(report_json)) = (report_file, report_json) {
+        outputs.push((report_file, report_json));
+    }
+    if let Some(static_asserts) = static_asserts {
+        outputs.push((static_asserts, lens.static_asserts().into_bytes()));
+    }
+    if let (Some(self_test), Some(self_test_code)) = (self_test, self_test_code) {
+        outputs.push((self_test, self_test_code.into_bytes()));
     }
 
-    debug!("done generating code, writing output");
+    debug!("writing output");
 
-    // assmeble the code
-    let section_title_comment_width = 80;
-    let spaces_per_tab = 4;
+    match output_file {
+        Some(output_file) => outputs.push((output_file, code)),
+        // the binary module is not text, hence written as is
+        None if output_format == OutputFormat::Wasm => std::io::stdout().write_all(&code)?,
+        None => write_output(None, std::str::from_utf8(&code)?)?,
+    }
 
-    // actually generate strings
-    let mut code_snippet_strings: Vec<String> = code_snippets
-        .iter()
-        .map(|s| match s {
-            CSnippet::Include(cinclude) => cinclude.generate(),
-            CSnippet::Func(cfunc) => {
-                cfunc.generate(comment, !only_prototype, function_decl_prefix.as_deref())
+    // compare instead of write
+    if check {
+        let mut up_to_date = true;
+        for (path, content) in outputs {
+            match check_file(&path, &content)? {
+                Some(diff) => {
+                    error!("{path:?} is not up to date");
+                    print!("{diff}");
+                    up_to_date = false;
+                }
+                None => info!("{path:?} is up to date"),
             }
-            CSnippet::Section(csection) => csection.generate(section_title_comment_width),
-            CSnippet::Newline => '\n'.into(),
-        })
-        .collect();
+        }
+        if !up_to_date {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
-    // retab with spaces
-    for snip in &mut code_snippet_strings {
-        let tab_space_replacement = " ".repeat(spaces_per_tab);
-        *snip = snip.replace('\t', &tab_space_replacement);
+    if let Some(depfile) = depfile {
+        let targets: Vec<_> = outputs.iter().map(|(path, _)| path).collect();
+        outputs.push((depfile, lens.depfile(&targets).into_bytes()));
+    }
 
-        // normalize newline endings
-        if !snip.ends_with("\n") {
-            snip.push('\n');
-        }
+    for (path, content) in outputs {
+        info!("writing output to {path:?}");
+        File::create(path)?.write_all(&content)?;
     }
 
-    // join it into a string
-    let final_c_code = code_snippet_strings.concat();
+    Ok(())
+}
 
+/// Write `content` to `output_file`, or to stdout if there is none
+fn write_output(output_file: Option<PathBuf>, content: &str) -> Result<()> {
     match output_file {
         // write to output file
         Some(output_file) => {
             let mut file = File::create(output_file)?;
-            file.write_all(final_c_code.as_bytes())?;
+            file.write_all(content.as_bytes())?;
         }
         // write to stdout
-        None => println!("{final_c_code}"),
+        None => println!("{content}"),
     }
 
     Ok(())
@@ -0,0 +1,324 @@
+//! Summary of what a generated lens does and does not cover
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use log::error;
+use serde::Serialize;
+
+use crate::code_gen::{FieldKind, LensLayout, RecordKind, TypeLayout};
+
+/// Where an item is declared, as reported by clang
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl SourceLocation {
+    /// The location `entity` is spelled at, if it is declared in a file
+    pub fn of(entity: &clang::Entity) -> Option<Self> {
+        let location = entity.get_location()?.get_spelling_location();
+        Some(Self {
+            file: location.file?.get_path(),
+            line: location.line,
+            column: location.column,
+        })
+    }
+}
+
+/// The kinds of items left out of the generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkippedKind {
+    /// A struct, union or enum, with all of its fields
+    Type,
+
+    /// One field of a struct or union
+    Field,
+}
+
+/// An item left out of the generated code due to an error, rather than due to a filter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedItem {
+    pub kind: SkippedKind,
+
+    /// Name of the type, or `type.field` for a field
+    pub name: String,
+
+    /// The error causing the item to be skipped
+    pub reason: String,
+    pub location: Option<SourceLocation>,
+}
+
+/// The locations of all types and fields of a layout, and the items skipped while gathering it or
+/// while generating code from it
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    /// Location per type name, and per `type.field` name
+    pub locations: BTreeMap<String, SourceLocation>,
+    pub skipped: Vec<SkippedItem>,
+}
+
+impl Coverage {
+    /// Note down the location of a type or `type.field`, if it is known
+    pub fn locate(&mut self, name: impl Into<String>, location: Option<SourceLocation>) {
+        if let Some(location) = location {
+            self.locations.insert(name.into(), location);
+        }
+    }
+
+    /// Note down a skipped item, see [`SkippedItem`]
+    pub fn skip(
+        &mut self,
+        kind: SkippedKind,
+        name: impl Into<String>,
+        reason: impl ToString,
+        location: Option<SourceLocation>,
+    ) {
+        self.skipped.push(SkippedItem {
+            kind,
+            name: name.into(),
+            reason: reason.to_string(),
+            location,
+        });
+    }
+}
+
+/// What a generated lens covers, per type and field
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationReport {
+    pub input_file: PathBuf,
+    pub target: String,
+
+    /// Whether no type or field was skipped
+    pub complete: bool,
+
+    /// The types accessors were generated for, though maybe not for all of their fields
+    pub types: Vec<TypeCoverage>,
+    pub skipped: Vec<SkippedItem>,
+}
+
+/// The coverage of one generated type
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeCoverage {
+    pub name: String,
+    pub kind: CoveredTypeKind,
+    pub location: Option<SourceLocation>,
+
+    /// The fields accessors were generated for, empty for enums
+    pub fields: Vec<FieldCoverage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoveredTypeKind {
+    Struct,
+    Union,
+    Enum,
+}
+
+/// The coverage of one generated field
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldCoverage {
+    /// The field's name, `.` separated for nested fields
+    pub name: String,
+    pub access: FieldAccess,
+    pub location: Option<SourceLocation>,
+}
+
+/// How the value of a field is accessed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FieldAccess {
+    /// Getters and setters converting the value
+    Value,
+
+    /// Only a pointer to the field's bytes, as no ABI compatible representation of its type is
+    /// known
+    Opaque { type_name: String },
+}
+
+impl GenerationReport {
+    /// Summarize the coverage of the code generated for `layout`
+    ///
+    /// Skipped items without a location get the one noted down for their name, if any.
+    pub fn new(input_file: PathBuf, layout: &LensLayout, coverage: &Coverage) -> Self {
+        let location = |name: &str| coverage.locations.get(name).cloned();
+        let is_skipped = |kind, name: &str| {
+            coverage
+                .skipped
+                .iter()
+                .any(|s| s.kind == kind && s.name == name)
+        };
+
+        let mut types = Vec::new();
+        for type_layout in &layout.types {
+            let (name, kind, fields) = match type_layout {
+                TypeLayout::Record(record) => {
+                    let kind = match record.kind {
+                        RecordKind::Struct => CoveredTypeKind::Struct,
+                        RecordKind::Union => CoveredTypeKind::Union,
+                    };
+                    (&record.name, kind, record.fields.as_slice())
+                }
+                TypeLayout::Enum(enum_) => (&enum_.name, CoveredTypeKind::Enum, [].as_slice()),
+            };
+            if is_skipped(SkippedKind::Type, name) {
+                continue;
+            }
+
+            let fields = fields
+                .iter()
+                .map(|field| (format!("{name}.{}", field.name()), field))
+                .filter(|(qualified_name, _)| !is_skipped(SkippedKind::Field, qualified_name))
+                .map(|(qualified_name, field)| FieldCoverage {
+                    name: field.name(),
+                    access: match &field.kind {
+                        FieldKind::Opaque { type_name, .. } => FieldAccess::Opaque {
+                            type_name: type_name.clone(),
+                        },
+                        _ => FieldAccess::Value,
+                    },
+                    location: location(&qualified_name),
+                })
+                .collect();
+
+            types.push(TypeCoverage {
+                name: name.clone(),
+                kind,
+                location: location(name),
+                fields,
+            });
+        }
+
+        Self {
+            input_file,
+            target: layout.target.triple.clone(),
+            complete: coverage.skipped.is_empty(),
+            types,
+            skipped: coverage
+                .skipped
+                .iter()
+                .cloned()
+                .map(|mut skipped| {
+                    skipped.location = skipped.location.or_else(|| location(&skipped.name));
+                    skipped
+                })
+                .collect(),
+        }
+    }
+
+    /// Log each skipped item as error
+    pub fn log_skipped(&self) {
+        for SkippedItem {
+            kind,
+            name,
+            reason,
+            location,
+        } in &self.skipped
+        {
+            let location = match location {
+                Some(SourceLocation { file, line, column }) => {
+                    format!(" at {}:{line}:{column}", file.display())
+                }
+                None => String::new(),
+            };
+            error!("skipped {kind:?} {name:?}{location}: {reason}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{Coverage, FieldAccess, GenerationReport, SkippedKind, SourceLocation};
+    use crate::code_gen::{
+        Endianness, FieldKind, FieldLayout, LensLayout, RecordKind, RecordLayout,
+        RepresentableCType, ScalarMeaning, TargetLayout, TypeLayout,
+    };
+
+    fn field(name: &str, kind: FieldKind) -> FieldLayout {
+        FieldLayout {
+            path: vec![name.to_owned()],
+            offset_bits: 0,
+            align_bytes: 4,
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_generation_report() {
+        let int = FieldKind::Scalar {
+            repr: RepresentableCType::Integer {
+                bytes: 4,
+                is_unsigned: false,
+            },
+            meaning: ScalarMeaning::Value,
+        };
+        let opaque = FieldKind::Opaque {
+            type_kind: "LongDouble".to_owned(),
+            type_name: "long double".to_owned(),
+            size_bytes: 16,
+        };
+        let record = |name: &str, fields| {
+            TypeLayout::Record(RecordLayout {
+                name: name.to_owned(),
+                c_type: name.to_owned(),
+                kind: RecordKind::Struct,
+                size_bytes: 16,
+                align_bytes: 16,
+                fields,
+            })
+        };
+        let layout = LensLayout {
+            target: TargetLayout {
+                triple: "wasm32-unknown-none".to_owned(),
+                endianness: Endianness::Little,
+                pointer_width_bytes: 4,
+            },
+            types: vec![
+                record("A", vec![field("a", int.clone()), field("b", opaque)]),
+                record("B", vec![field("a", int.clone()), field("c", int)]),
+                record("C", vec![]),
+            ],
+            constants: Vec::new(),
+            anonymous_enums: Vec::new(),
+        };
+
+        let location = |line| SourceLocation {
+            file: PathBuf::from("a.h"),
+            line,
+            column: 1,
+        };
+        let mut coverage = Coverage::default();
+        coverage.locate("A", Some(location(1)));
+        coverage.locate("A.a", Some(location(2)));
+        coverage.locate("B.c", Some(location(6)));
+        coverage.skip(SkippedKind::Field, "B.c", "unsupported", None);
+        coverage.skip(SkippedKind::Field, "B.d", "unsupported", Some(location(7)));
+        coverage.skip(SkippedKind::Type, "C", "unsupported", None);
+
+        let report = GenerationReport::new(PathBuf::from("a.h"), &layout, &coverage);
+        assert!(!report.complete);
+
+        let type_names: Vec<_> = report.types.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(type_names, ["A", "B"]);
+        assert_eq!(report.types[0].location, Some(location(1)));
+        assert_eq!(report.types[0].fields[0].location, Some(location(2)));
+        assert_eq!(report.types[0].fields[0].access, FieldAccess::Value);
+        assert_eq!(
+            report.types[0].fields[1].access,
+            FieldAccess::Opaque {
+                type_name: "long double".to_owned()
+            }
+        );
+
+        let field_names: Vec<_> = report.types[1].fields.iter().map(|f| &f.name).collect();
+        assert_eq!(field_names, ["a"]);
+        assert_eq!(report.skipped.len(), 3);
+        assert_eq!(report.skipped[0].location, Some(location(6)));
+        assert_eq!(report.skipped[1].location, Some(location(7)));
+    }
+}
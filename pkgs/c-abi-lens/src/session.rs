@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use clang::{Clang, EntityKind, Index, diagnostic::Severity};
+use color_eyre::{Result, eyre::eyre};
+use log::{debug, error, warn};
+use regex::Regex;
+
+use crate::code_gen::{LensLayout, TypeFilter, evaluate_macros};
+use crate::report::Coverage;
+
+/// A diagnostic reported by libclang while parsing, e.g. a warning or a syntax error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClangDiagnostic {
+    /// Whether the diagnostic is an error, rather than a warning or a note
+    pub is_error: bool,
+
+    /// The message, prefixed by the source location it refers to
+    pub message: String,
+}
+
+/// Everything gathered from parsing one input file
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    /// The layout of all selected types, as well as the value of all selected macros
+    pub layout: LensLayout,
+
+    /// The diagnostics libclang reported while parsing
+    pub diagnostics: Vec<ClangDiagnostic>,
+
+    /// The input file followed by every file it includes, directly or transitively, each once
+    pub included_files: Vec<PathBuf>,
+
+    /// The locations of the gathered types and fields, and the ones skipped while gathering
+    pub coverage: Coverage,
+}
+
+/// A loaded instance of libclang, reusable for parsing any number of files
+///
+/// libclang can only be loaded once per process at a time, and only be used from the thread that
+/// loaded it. Hence create one session per thread and pass it to every [`Builder`](crate::Builder)
+/// instead of creating a new one for each file; creating a second session while the first one is
+/// alive fails.
+pub struct ClangSession {
+    clang: Clang,
+}
+
+impl ClangSession {
+    /// Load libclang
+    pub fn new() -> Result<Self> {
+        debug!("initializing clang");
+        let clang = Clang::new().map_err(|e| eyre!("error initializing clang:\n{e}"))?;
+        Ok(Self { clang })
+    }
+
+    /// Parse `input_file` and gather the layout of all types in it selected by the `type_filter`,
+    /// as well as the value of all macros matching the `macro_patterns`
+    ///
+    /// All diagnostics libclang reports while parsing are logged and returned alongside the layout.
+    /// Parsing does not fail on errors in the input file, but the layout might be incomplete then.
+    pub fn parse_layout(
+        &self,
+        input_file: &Path,
+        clang_args: &[String],
+        type_filter: &TypeFilter,
+        macro_patterns: &[Regex],
+    ) -> Result<ParsedFile> {
+        // Create a new `Index`, diagnostics are logged below instead of printed by libclang
+        let index = Index::new(&self.clang, false, false);
+
+        // Parse a source file into a translation unit
+        let mut parser = index.parser(input_file);
+
+        // record macro definitions and inclusion directives
+        parser.detailed_preprocessing_record(true);
+
+        // Pass all clang args
+        debug!("clang args: {clang_args:#?}");
+        parser.arguments(clang_args);
+
+        // parse the code into a translation unit
+        debug!("parsing {:?}", input_file);
+        let tu = parser.parse()?;
+
+        let mut diagnostics = Vec::new();
+        for diagnostic in tu.get_diagnostics() {
+            let is_error = match diagnostic.get_severity() {
+                Severity::Ignored | Severity::Note => continue,
+                Severity::Warning => false,
+                Severity::Error | Severity::Fatal => true,
+            };
+            let message = diagnostic.formatter().format();
+            match is_error {
+                true => error!("clang: {message}"),
+                false => warn!("clang: {message}"),
+            }
+            diagnostics.push(ClangDiagnostic { is_error, message });
+        }
+
+        // files included via `-include` or `-imacros` have no inclusion directive
+        let mut included_files = vec![input_file.to_owned()];
+        for forced_include in forced_includes(clang_args) {
+            match tu.get_file(forced_include) {
+                Some(file) if !included_files.contains(&file.get_path()) => {
+                    included_files.push(file.get_path())
+                }
+                Some(_) => {}
+                None => {
+                    warn!("unable to locate {forced_include:?}, it is not listed as dependency")
+                }
+            }
+        }
+
+        // the inclusion directives of all files are children of the translation unit
+        for included_file in tu
+            .get_entity()
+            .get_children()
+            .into_iter()
+            .filter(|e| e.get_kind() == EntityKind::InclusionDirective)
+            .filter_map(|e| e.get_file())
+            .map(|f| f.get_path())
+        {
+            if !included_files.contains(&included_file) {
+                included_files.push(included_file);
+            }
+        }
+        debug!("included files: {included_files:#?}");
+
+        let mut coverage = Coverage::default();
+        let mut layout = LensLayout::from_translation_unit(&tu, type_filter, &mut coverage)?;
+        layout.constants = evaluate_macros(&index, &tu, input_file, clang_args, macro_patterns)?;
+
+        Ok(ParsedFile {
+            layout,
+            diagnostics,
+            included_files,
+            coverage,
+        })
+    }
+}
+
+/// The files included ahead of the input file via `-include <FILE>` or `-imacros <FILE>`, or their
+/// `--include=<FILE>` and `--imacros=<FILE>` forms
+fn forced_includes(clang_args: &[String]) -> Vec<&str> {
+    let mut paths = Vec::new();
+    let mut args = clang_args.iter();
+    while let Some(arg) = args.next() {
+        if matches!(arg.as_str(), "-include" | "-imacros") {
+            paths.extend(args.next().map(String::as_str));
+        } else if let Some(path) = arg
+            .strip_prefix("--include=")
+            .or_else(|| arg.strip_prefix("--imacros="))
+        {
+            paths.push(path);
+        }
+    }
+    paths
+}
@@ -72,7 +72,7 @@ impl ClangSubmission {
 
         // prepare the final clang_arguments, by extend the environment variable based ones with the
         // `clang_args` passed to this function
-        let mut default_clang_args = c_abi_lens::clang_args_from_env();
+        let mut default_clang_args = c_abi_lens::clang_args_from_env()?;
         default_clang_args.extend(clang_args.iter().map(|s| s.to_string()));
         let clang_args = default_clang_args;
         eprintln!("clang args: {clang_args:#?}");
@@ -0,0 +1,33 @@
+use c_abi_lens::{Builder, ClangSession};
+
+#[macro_use]
+extern crate test_bin;
+
+const SMOKE_TEST_FILE: &str = "tests/smoke_test.h";
+
+/// Verify that the library generates the same code as the binary, reusing one clang session
+#[test]
+fn builder_matches_binary() {
+    let session = ClangSession::new().unwrap();
+
+    for args in [&["--comment"][..], &["--comment", "--deref-helpers"]] {
+        // actual processing, via the binary
+        let output = get_test_bin!("c-abi-lens")
+            .args(args)
+            .arg(SMOKE_TEST_FILE)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        // actual processing, via the library
+        let code = Builder::new(SMOKE_TEST_FILE)
+            .clang_args(c_abi_lens::clang_args_from_env().unwrap())
+            .comment(true)
+            .deref_helpers(args.contains(&"--deref-helpers"))
+            .generate(&session)
+            .unwrap();
+
+        // check the results, the binary appends a newline when printing
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), code + "\n");
+    }
+}
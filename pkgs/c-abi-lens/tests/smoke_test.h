@@ -67,3 +67,96 @@ typedef struct StructThatWillBeTypedefed {
   short a;
   float b;
 } StructTypedef;
+
+struct StructWithBitfields {
+  unsigned char a : 3;
+  signed int b : 7;
+  unsigned int : 4;
+  unsigned int c : 17;
+  unsigned long long d : 40;
+  char e;
+};
+
+struct __attribute__((packed)) PackedStructWithBitfields {
+  char a;
+  long long b : 33;
+  short c : 9;
+  unsigned char d : 1;
+};
+
+struct StructWithBoolBitfield {
+  _Bool a : 1;
+  unsigned char b : 3;
+  _Bool c : 1;
+};
+
+struct DeeplyNestedStruct {
+  char a;
+  struct NestedStruct b;
+  struct {
+    short c;
+    struct StructWithBitfields d;
+  } e;
+  struct {
+    long f;
+    unsigned int g : 5;
+  };
+};
+
+struct TaggedUnion {
+  enum SomeEnum tag;
+  union {
+    int i;
+    float f;
+    struct IntegerWithPadding s;
+  } payload;
+};
+
+typedef StructTypedef AnotherStructTypedef;
+
+typedef struct SameNameTypedef {
+  int a;
+} SameNameTypedef;
+
+typedef union {
+  unsigned int a;
+  float b;
+} AnonymousUnionTypedef;
+
+typedef void (*EntryPoint)(void);
+
+typedef struct {
+  char NAME[30];
+  void (*ENTRY_POINT)(void);
+  unsigned int STACK_SIZE;
+  EntryPoint ERROR_HANDLER;
+  int (*CALLBACK)(int, const char *);
+} StructWithFunctionPointers;
+
+typedef enum {
+  NO_ERROR = 0,
+  NO_ACTION = 1,
+  NOT_AVAILABLE = 2,
+  INVALID_PARAM = 3,
+  TIMED_OUT = 7,
+  LAST_RETURN_CODE = TIMED_OUT,
+  NEGATIVE_CODE = -5,
+} ReturnCodeType;
+
+struct StructWithTypedefEnum {
+  ReturnCodeType a;
+  enum SomeEnum b : 2;
+};
+
+struct StructWithArrayOfStructs {
+  unsigned short a;
+  struct IntegerWithArray b[4];
+  AnonymousStructTypedef c[3][2];
+};
+
+#define SMOKE_TEST_LIMIT (4 * sizeof(struct IntegerWithPadding))
+#define SMOKE_TEST_NEGATIVE -42
+#define SMOKE_TEST_STRING "not an integer"
+#ifndef SMOKE_TEST_DEFAULT
+#define SMOKE_TEST_DEFAULT 8
+#endif
@@ -1,7 +1,7 @@
 // #[clippy::deny()]
 
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[macro_use]
 extern crate test_bin;
@@ -70,3 +70,622 @@ gen_test! {"prefix" = ["funky_prefix"], "comment", "only-prototype"}
 gen_test! {"function-decl-prefix" = [ "inline" ], "comment"}
 gen_test! {"function-decl-prefix" = [ "static" "inline" ], "comment"}
 gen_test! {"function-decl-prefix" = [ "static" "inline" ], "comment", "endianness-swap"}
+gen_test! {"deref-helpers", "comment"}
+gen_test! {"deref-helpers", "comment", "endianness-swap"}
+gen_test! {"main-file-only", "comment"}
+
+/// Verify that byte-swapping accessors address the field and reverse each element as a whole
+#[test]
+fn generate_endianness_swap_accessors() {
+    let output = get_test_bin!("c-abi-lens")
+        .arg("--endianness-swap")
+        .arg(SMOKE_TEST_FILE)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let code = String::from_utf8(output.stdout).unwrap();
+
+    // `PackedStruct.d` is an array of 8 byte elements at offset 10
+    assert!(code.contains(
+        "((uint8_t *)dst)[i] = ((uint8_t *)((uint8_t *) struct_base_addr + 10))[i + 7 - 2 * (i % 8)];"
+    ));
+    assert!(code.contains(
+        "((uint8_t *)((uint8_t *) struct_base_addr + 10))[i] = ((uint8_t *)src)[i + 7 - 2 * (i % 8)];"
+    ));
+
+    // `FloatWithPadding.a` and `FloatWithPadding.b` are swapped on an integer copy of their bits
+    assert!(code.contains("bytes = bswap_32(bytes);"));
+    assert!(code.contains("bytes = bswap_64(bytes);"));
+}
+
+/// Verify that the Rust output compiles as `no_std` library without any warnings
+#[test]
+fn generate_output_format_rust_comment_deref_helpers() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE);
+
+    // where to generate to
+    let out_dir = tempfile::tempdir().unwrap();
+    let module_file = out_dir.path().join("lens.rs");
+    let lib_file = out_dir.path().join("lib.rs");
+
+    // actual processing
+    let _output = get_test_bin!("c-abi-lens")
+        .args(["--output-format", "rust", "--comment", "--deref-helpers"])
+        .arg("--output-file")
+        .arg(module_file.as_os_str())
+        .arg(input_file)
+        .output()
+        .unwrap();
+
+    std::fs::write(
+        &lib_file,
+        "#![no_std]\n#![forbid(unsafe_code)]\n#![deny(warnings)]\ninclude!(\"lens.rs\");\n",
+    )
+    .unwrap();
+
+    // check the results
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+    let status = std::process::Command::new(rustc)
+        .args(["--edition", "2024", "--crate-type", "lib", "--out-dir"])
+        .arg(out_dir.path())
+        .arg(&lib_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// Verify that the JSON output is valid JSON of the current schema version
+#[test]
+fn generate_output_format_json() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE);
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .args(["--output-format", "json"])
+        .arg(input_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["schema_version"], 1);
+    assert!(!json["types"].as_array().unwrap().is_empty());
+}
+
+/// Verify that the static assertions hold when compiled alongside the input file
+#[test]
+fn generate_static_asserts_comment() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE);
+
+    // where to generate to
+    let static_asserts_file = tempfile::Builder::new()
+        .prefix("static_asserts")
+        .suffix(".h")
+        .tempfile()
+        .unwrap();
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .arg("--comment")
+        .arg("--static-asserts")
+        .arg(static_asserts_file.path().as_os_str())
+        .arg("--output-file")
+        .arg("/dev/null")
+        .arg(&input_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results, the input file is included by its file name
+    let include_dir = format!("-I{}", input_file.parent().unwrap().display());
+    let clang_args: Vec<_> = DEFAULT_WARNING_FLAGS
+        .iter()
+        .copied()
+        .chain([include_dir.as_str()])
+        .collect();
+    check_c_file_parses(static_asserts_file.path(), &clang_args).unwrap();
+}
+
+/// Verify that the self-test program compiles without any warnings and passes on the host
+#[test]
+fn generate_self_test_comment() {
+    // the layout is that of the host as no target is passed to clang
+    check_self_test_passes(SMOKE_TEST_FILE.as_ref(), &[]);
+}
+
+/// Verify that `_Bool` bit-fields are set and read back by the self-test, on the host as well as
+/// on a foreign target of the other byte order
+#[test]
+fn generate_self_test_bool_bitfield() {
+    let mut input_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    write!(
+        input_file,
+        "struct BoolBitfield {{ _Bool a : 1;\n  _Bool b : 1;\n  unsigned char c : 6; }};"
+    )
+    .unwrap();
+
+    for clang_args in [&[][..], &["--target=powerpc-unknown-eabi"]] {
+        let self_test = check_self_test_passes(input_file.path(), clang_args);
+        assert!(self_test.contains("set__BoolBitfield__b("));
+        assert!(self_test.contains("get__BoolBitfield__b("));
+    }
+}
+
+/// Foreign targets of either byte order, to generate code for on any host
+const TARGET_MATRIX: &[&str] = &[
+    "wasm32-unknown-none",
+    "x86_64-unknown-linux-gnu",
+    "powerpc-unknown-eabi",
+    "powerpc64-unknown-linux-gnu",
+    "s390x-unknown-linux-gnu",
+];
+
+/// Verify that the self-test passes on the host for foreign targets of either byte order
+#[test]
+fn generate_self_test_comment_target_matrix() {
+    for target in TARGET_MATRIX {
+        eprintln!("target: {target}");
+        check_self_test_passes(SMOKE_TEST_FILE.as_ref(), &[&format!("--target={target}")]);
+    }
+}
+
+/// Verify that bytes are swapped exactly if the foreign target differs from the host in byte order
+#[test]
+fn generate_comment_target_matrix() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE);
+
+    for (target, host_target) in TARGET_MATRIX.iter().flat_map(|t| {
+        [
+            (t, "x86_64-unknown-linux-gnu"),
+            (t, "powerpc-unknown-linux-gnu"),
+        ]
+    }) {
+        eprintln!("target: {target}, host target: {host_target}");
+
+        // where to generate to
+        let prototype_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+
+        // actual processing
+        let output = get_test_bin!("c-abi-lens")
+            .args(["--comment", "--host-target", host_target])
+            .arg("--output-file")
+            .arg(prototype_file.path().as_os_str())
+            .arg(&input_file)
+            .arg("--")
+            .arg(format!("--target={target}"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        // check the results
+        check_c_file_parses(prototype_file.path(), DEFAULT_WARNING_FLAGS).unwrap();
+
+        let is_big_endian =
+            |triple: &str| ["powerpc", "s390x"].iter().any(|a| triple.starts_with(a));
+        let code = std::fs::read_to_string(prototype_file.path()).unwrap();
+        let swaps = is_big_endian(target) != is_big_endian(host_target);
+        assert_eq!(code.contains("#include<byteswap.h>"), swaps);
+        assert_eq!(
+            ["bswap_16(", "bswap_32(", "bswap_64("]
+                .iter()
+                .any(|call| code.contains(call)),
+            swaps
+        );
+    }
+}
+
+/// Generate the self-test program for `input_file` and the given `clang_args`, then compile and run it
+/// on the host
+///
+/// Returns the source of the self-test program.
+fn check_self_test_passes(input_file: &Path, clang_args: &[&str]) -> String {
+    // where to generate to
+    let out_dir = tempfile::tempdir().unwrap();
+    let self_test_file = out_dir.path().join("self_test.c");
+    let self_test_bin = out_dir.path().join("self_test");
+
+    // actual processing, bytes are swapped if the foreign target differs from the host
+    let output = get_test_bin!("c-abi-lens")
+        .arg("--comment")
+        .arg("--self-test")
+        .arg(self_test_file.as_os_str())
+        .arg("--output-file")
+        .arg("/dev/null")
+        .arg(input_file)
+        .arg("--")
+        .args(clang_args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    check_c_file_parses(&self_test_file, DEFAULT_WARNING_FLAGS).unwrap();
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = std::process::Command::new(cc)
+        .args(DEFAULT_WARNING_FLAGS)
+        .arg("-Werror")
+        .arg("-o")
+        .arg(&self_test_bin)
+        .arg(&self_test_file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let status = std::process::Command::new(&self_test_bin).status().unwrap();
+    assert!(status.success());
+
+    std::fs::read_to_string(&self_test_file).unwrap()
+}
+
+/// Verify that only the selected types and fields are considered
+#[test]
+fn generate_output_format_json_filtered() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE);
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .args(["--output-format", "json", "--main-file-only"])
+        .args([
+            "--include-type",
+            "Struct.*",
+            "--include-type",
+            "NestedStruct",
+        ])
+        .args(["--exclude-type", "StructWithPointer"])
+        .args(["--exclude-field", "NestedStruct\\.a"])
+        .arg(input_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let types = json["types"].as_array().unwrap();
+    let type_names: Vec<_> = types.iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert!(type_names.contains(&"StructWithEnum"));
+    assert!(type_names.contains(&"NestedStruct"));
+    assert!(!type_names.contains(&"StructWithPointer"));
+    assert!(!type_names.contains(&"SomeEnum"));
+
+    let nested_struct = types.iter().find(|t| t["name"] == "NestedStruct").unwrap();
+    for field in nested_struct["fields"].as_array().unwrap() {
+        assert_ne!(field["path"][0], "a");
+    }
+}
+
+/// Verify that selected macros are evaluated, respecting overrides of defaults
+#[test]
+fn generate_output_format_json_macros() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE);
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .args(["--output-format", "json", "--macro", "SMOKE_TEST_.*"])
+        .arg(input_file)
+        .args(["--", "-DSMOKE_TEST_DEFAULT=16"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let constants = json["constants"].as_array().unwrap();
+    let value_of = |name: &str| {
+        constants
+            .iter()
+            .find(|c| c["name"] == name)
+            .map(|c| c["signed_value"].as_i64().unwrap())
+    };
+    assert!(value_of("SMOKE_TEST_LIMIT").unwrap() > 0);
+    assert_eq!(value_of("SMOKE_TEST_NEGATIVE"), Some(-42));
+    assert_eq!(value_of("SMOKE_TEST_DEFAULT"), Some(16));
+    assert_eq!(value_of("SMOKE_TEST_STRING"), None);
+}
+
+/// Verify that selected macros are exported as WebAssembly globals, deterministically
+#[test]
+fn generate_output_format_wat_macros() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE);
+
+    // actual processing, twice
+    let outputs: Vec<_> = (0..2)
+        .map(|_| {
+            get_test_bin!("c-abi-lens")
+                .args(["--output-format", "wat", "--macro", "SMOKE_TEST_.*"])
+                .arg(&input_file)
+                .output()
+                .unwrap()
+        })
+        .collect();
+    assert!(outputs.iter().all(|o| o.status.success()));
+
+    // check the results
+    assert_eq!(outputs[0].stdout, outputs[1].stdout);
+    let wat = String::from_utf8(outputs[0].stdout.clone()).unwrap();
+    assert!(wat.contains(
+        "(global $SMOKE_TEST_NEGATIVE (export \"SMOKE_TEST_NEGATIVE\") i32 (i32.const -42))"
+    ));
+    assert!(wat.contains("(export \"SMOKE_TEST_DEFAULT\") i32 (i32.const 8))"));
+    assert!(!wat.contains("SMOKE_TEST_STRING"));
+}
+
+/// Verify that `SYSTEM_LIMIT_*` globals are `i32` unless their value needs 8 bytes, unlike the
+/// `i64` globals of the former awk script
+#[test]
+fn generate_output_format_wat_value_types() {
+    // file to read in
+    let mut input_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    writeln!(input_file, "#define SYSTEM_LIMIT_NUMBER_OF_PARTITIONS 32").unwrap();
+    writeln!(input_file, "#define SYSTEM_LIMIT_MAX_TIME 0x100000000").unwrap();
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .args(["--output-format", "wat", "--macro", "SYSTEM_LIMIT_.*"])
+        .arg(input_file.path())
+        .args(["--", "--target=wasm32-unknown-wasi"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    let wat = String::from_utf8(output.stdout).unwrap();
+    assert!(wat.contains("(export \"SYSTEM_LIMIT_NUMBER_OF_PARTITIONS\") i32 (i32.const 32))"));
+    assert!(wat.contains("(export \"SYSTEM_LIMIT_MAX_TIME\") i64 (i64.const 4294967296))"));
+}
+
+/// Verify that clang args are taken from a compilation database and from response files
+#[test]
+fn generate_output_format_json_compile_commands() {
+    // file to read in
+    let input_file = PathBuf::from(SMOKE_TEST_FILE).canonicalize().unwrap();
+
+    // a compilation database overriding the default, and a response file with a quoted argument
+    let dir = tempfile::tempdir().unwrap();
+    let database = serde_json::json!([{
+        "directory": dir.path(),
+        "file": input_file,
+        "arguments": ["clang", "-DSMOKE_TEST_DEFAULT=16", "-c", "-o", "smoke_test.o", input_file],
+    }]);
+    std::fs::write(
+        dir.path().join("compile_commands.json"),
+        database.to_string(),
+    )
+    .unwrap();
+    let response_file = dir.path().join("args.rsp");
+    std::fs::write(&response_file, "'-DSMOKE_TEST_EXTRA=(1 + 2)'\n").unwrap();
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .args(["--output-format", "json", "--macro", "SMOKE_TEST_.*"])
+        .arg("--compile-commands")
+        .arg(dir.path())
+        .arg(&input_file)
+        .arg("--")
+        .arg(format!("@{}", response_file.display()))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let constants = json["constants"].as_array().unwrap();
+    let value_of = |name: &str| {
+        constants
+            .iter()
+            .find(|c| c["name"] == name)
+            .map(|c| c["signed_value"].as_i64().unwrap())
+    };
+    assert_eq!(value_of("SMOKE_TEST_DEFAULT"), Some(16));
+    assert_eq!(value_of("SMOKE_TEST_EXTRA"), Some(3));
+}
+
+/// Verify that `--check` accepts up to date outputs and reports drifted ones with a diff
+#[test]
+fn check_output_file() {
+    // file to read in, absolute so the recorded path depends on `--relative-to`
+    let input_file = PathBuf::from(SMOKE_TEST_FILE).canonicalize().unwrap();
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+
+    // where to generate to
+    let output_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    let run = |check: bool| {
+        get_test_bin!("c-abi-lens")
+            .args(["--comment", "--relative-to", manifest_dir])
+            .args(check.then_some("--check"))
+            .arg("--output-file")
+            .arg(output_file.path())
+            .arg(&input_file)
+            .output()
+            .unwrap()
+    };
+
+    // actual processing, a missing output is not up to date
+    std::fs::remove_file(output_file.path()).unwrap();
+    assert_eq!(run(true).status.code(), Some(1));
+    assert!(run(false).status.success());
+    assert!(run(true).status.success());
+
+    // check the results
+    let code = std::fs::read_to_string(output_file.path()).unwrap();
+    assert!(code.contains("\"tests/smoke_test.h\""));
+    assert!(!code.contains(manifest_dir));
+
+    // a drifted output is reported with a diff, and left alone
+    let drifted = code.replacen("uint8_t", "int8_t", 1);
+    std::fs::write(output_file.path(), &drifted).unwrap();
+    let output = run(true);
+    assert_eq!(output.status.code(), Some(1));
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert!(diff.starts_with("---"), "{diff}");
+    assert!(diff.contains("+") && diff.contains("uint8_t"), "{diff}");
+    assert_eq!(
+        std::fs::read_to_string(output_file.path()).unwrap(),
+        drifted
+    );
+}
+
+/// Verify that the dependency file lists the input file and the headers included by it
+#[test]
+fn generate_depfile() {
+    // file to read in, including the smoke test header
+    let mut input_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    let smoke_test_file = PathBuf::from(SMOKE_TEST_FILE).canonicalize().unwrap();
+    writeln!(input_file, "#include \"{}\"", smoke_test_file.display()).unwrap();
+
+    // where to generate to
+    let output_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    let depfile = tempfile::Builder::new().suffix(".d").tempfile().unwrap();
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .arg("--output-file")
+        .arg(output_file.path())
+        .arg("--depfile")
+        .arg(depfile.path())
+        .arg(input_file.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    let depfile = std::fs::read_to_string(depfile.path()).unwrap();
+    let (targets, dependencies) = depfile.split_once(':').unwrap();
+    assert_eq!(targets, output_file.path().to_string_lossy());
+    let dependencies: Vec<_> = dependencies
+        .lines()
+        .map(|l| l.trim().trim_end_matches('\\').trim_end())
+        .filter(|l| !l.is_empty())
+        .collect();
+    assert_eq!(dependencies[0], input_file.path().to_string_lossy());
+    assert!(
+        dependencies.contains(&&*smoke_test_file.to_string_lossy()),
+        "{depfile}"
+    );
+}
+
+/// Verify that the depfile lists a header included via `-include`, which has no inclusion directive
+#[test]
+fn generate_depfile_forced_include() {
+    // file to read in, and a header included ahead of it
+    let mut input_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    writeln!(input_file, "struct Configured {{ CONFIG_TYPE a; }};").unwrap();
+    let mut forced_include = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    writeln!(forced_include, "#define CONFIG_TYPE int").unwrap();
+    let forced_include_path = forced_include.path().canonicalize().unwrap();
+
+    // where to generate to
+    let output_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    let depfile = tempfile::Builder::new().suffix(".d").tempfile().unwrap();
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .arg("--output-file")
+        .arg(output_file.path())
+        .arg("--depfile")
+        .arg(depfile.path())
+        .arg(input_file.path())
+        .arg("--")
+        .arg("-include")
+        .arg(&forced_include_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // check the results
+    let depfile = std::fs::read_to_string(depfile.path()).unwrap();
+    assert!(
+        depfile.contains(&*forced_include_path.to_string_lossy()),
+        "{depfile}"
+    );
+}
+
+/// Verify that the report lists generated, opaque and skipped fields, and that `--strict` fails on
+/// skipped ones
+#[test]
+fn generate_report_strict() {
+    // file to read in, with a flexible array member which is skipped
+    let mut input_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    writeln!(input_file, "struct Covered {{ int a; long double b; }};").unwrap();
+    writeln!(
+        input_file,
+        "struct Flexible {{ int length;\n  int tail[]; }};"
+    )
+    .unwrap();
+
+    // where to generate to
+    let dir = tempfile::tempdir().unwrap();
+    let output_file = dir.path().join("lens.h");
+    let report_file = dir.path().join("report.json");
+    let run = |strict: bool| {
+        get_test_bin!("c-abi-lens")
+            .args(strict.then_some("--strict"))
+            .arg("--output-file")
+            .arg(&output_file)
+            .arg("--report")
+            .arg(&report_file)
+            .arg(input_file.path())
+            .output()
+            .unwrap()
+    };
+
+    // actual processing, strict mode writes the report but not the lens
+    assert!(!run(true).status.success());
+    assert!(!output_file.exists());
+    assert!(report_file.exists());
+    assert!(run(false).status.success());
+    assert!(output_file.exists());
+
+    // check the results
+    let report: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&report_file).unwrap()).unwrap();
+    assert_eq!(report["complete"], false);
+
+    let types = report["types"].as_array().unwrap();
+    let covered = types.iter().find(|t| t["name"] == "Covered").unwrap();
+    assert_eq!(covered["location"]["line"], 1);
+    assert_eq!(covered["fields"][0]["access"]["kind"], "value");
+    assert_eq!(covered["fields"][1]["access"]["kind"], "opaque");
+    let flexible = types.iter().find(|t| t["name"] == "Flexible").unwrap();
+    assert_eq!(flexible["fields"].as_array().unwrap().len(), 1);
+
+    let skipped = report["skipped"].as_array().unwrap();
+    assert_eq!(skipped.len(), 1);
+    assert_eq!(skipped[0]["kind"], "field");
+    assert_eq!(skipped[0]["name"], "Flexible.tail");
+    assert_eq!(skipped[0]["location"]["line"], 3);
+}
+
+/// Verify that the enumerators of an anonymous enum become constants, rather than failing `--strict`
+#[test]
+fn generate_anonymous_enum_strict() {
+    // file to read in
+    let mut input_file = tempfile::Builder::new().suffix(".h").tempfile().unwrap();
+    writeln!(input_file, "enum {{ X = 1 }};").unwrap();
+
+    // where to generate to
+    let dir = tempfile::tempdir().unwrap();
+    let output_file = dir.path().join("lens.h");
+
+    // actual processing
+    let output = get_test_bin!("c-abi-lens")
+        .arg("--strict")
+        .arg("--output-file")
+        .arg(&output_file)
+        .arg(input_file.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    // check the results
+    let lens = std::fs::read_to_string(&output_file).unwrap();
+    assert!(lens.contains("#define cal_enum__X "), "{lens}");
+}